@@ -0,0 +1,96 @@
+//! Compares the collision broadphase grid against the brute-force nested loop
+//! it replaced, for bullet counts well past what the game spawns today.
+//!
+//! Run with `cargo bench --bench broadphase`.
+
+// Benches build without the test harness, which leaves the module's unit
+// tests out but not their helpers
+#[path = "../src/game/broadphase.rs"]
+#[allow(unused)]
+mod broadphase;
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bevy::math::Vec2;
+use bevy::math::bounding::{Aabb2d, IntersectsVolume};
+use rand::prelude::*;
+
+use broadphase::Grid;
+
+const CELL_SIZE: f32 = 64.;
+const ITERATIONS: u32 = 20;
+const BULLET_COUNTS: [usize; 5] = [10, 100, 1_000, 5_000, 20_000];
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0);
+
+    // A full formation of 15 x 10 enemies
+    let targets: Vec<Aabb2d> = (0..10)
+        .flat_map(|col| (0..15).map(move |row| (col, row)))
+        .map(|(col, row)| {
+            let center = Vec2::new(col as f32 * 50. - 250., row as f32 * 50. + 100.);
+            Aabb2d::new(center, Vec2::splat(10.))
+        })
+        .collect();
+
+    println!("{:>8} {:>14} {:>14}", "bullets", "nested loop", "grid");
+    for bullets in BULLET_COUNTS {
+        let bullets: Vec<Aabb2d> = (0..bullets)
+            .map(|_| {
+                let center = Vec2::new(
+                    rng.random_range(-400. ..400.),
+                    rng.random_range(-300. ..300.),
+                );
+                Aabb2d::new(center, Vec2::splat(2.5))
+            })
+            .collect();
+
+        let mut grid = Grid::new(CELL_SIZE);
+        let nested_loop_time = time(|| nested_loop(&bullets, &targets));
+        let grid_time = time(|| broadphase(&mut grid, &bullets, &targets));
+        assert_eq!(
+            nested_loop(&bullets, &targets),
+            broadphase(&mut grid, &bullets, &targets)
+        );
+
+        println!(
+            "{:>8} {:>14?} {:>14?}",
+            bullets.len(),
+            nested_loop_time,
+            grid_time
+        );
+    }
+}
+
+fn time(mut f: impl FnMut() -> usize) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn nested_loop(bullets: &[Aabb2d], targets: &[Aabb2d]) -> usize {
+    bullets
+        .iter()
+        .filter(|bullet| targets.iter().any(|target| bullet.intersects(target)))
+        .count()
+}
+
+/// Includes rebuilding the grid, as the game does every tick.
+fn broadphase(grid: &mut Grid<usize>, bullets: &[Aabb2d], targets: &[Aabb2d]) -> usize {
+    grid.clear();
+    for (i, &target) in targets.iter().enumerate() {
+        grid.insert(target, i);
+    }
+
+    let mut candidates = Vec::new();
+    bullets
+        .iter()
+        .filter(|bullet| {
+            grid.query(bullet, &mut candidates);
+            !candidates.is_empty()
+        })
+        .count()
+}
@@ -0,0 +1,102 @@
+use crate::GameAssets;
+use crate::GameState;
+use crate::despawn_screen;
+use crate::profile::{Achievement, Profile};
+use crate::ui::{button, text};
+
+use bevy::prelude::*;
+
+const UNLOCKED_COLOR: Color = Color::srgb(1., 0.8, 0.2);
+const LOCKED_COLOR: Color = Color::srgb(0.4, 0.4, 0.4);
+
+pub fn achievements_screen_plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameState::Achievements), setup_achievements)
+        .add_systems(
+            Update,
+            button_interaction.run_if(in_state(GameState::Achievements)),
+        )
+        .add_systems(
+            OnExit(GameState::Achievements),
+            despawn_screen::<OnAchievementsScreen>,
+        );
+}
+
+#[derive(Component)]
+struct OnAchievementsScreen;
+
+#[derive(Component)]
+struct BackButton;
+
+fn setup_achievements(mut commands: Commands, assets: Res<GameAssets>, profile: Res<Profile>) {
+    let root = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::SpaceEvenly,
+                ..default()
+            },
+            OnAchievementsScreen,
+        ))
+        .id();
+
+    // Lifetime totals
+    let minutes = (profile.longest_survival / 60.) as u32;
+    let seconds = profile.longest_survival as u32 % 60;
+    commands.spawn((
+        text(
+            format!(
+                "Runs: {}  Kills: {}\nBest accuracy: {:.0}%  Longest run: {minutes}:{seconds:02}",
+                profile.runs_played,
+                profile.total_kills,
+                profile.best_accuracy * 100.
+            ),
+            14.0,
+            &assets,
+        ),
+        ChildOf(root),
+    ));
+
+    commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ChildOf(root),
+        ))
+        .with_children(|parent| {
+            for achievement in Achievement::ALL {
+                let unlocked = profile.achievements.contains(&achievement);
+                parent.spawn((
+                    text(
+                        format!("{} - {}", achievement.name(), achievement.description()),
+                        14.0,
+                        &assets,
+                    ),
+                    TextColor(if unlocked {
+                        UNLOCKED_COLOR
+                    } else {
+                        LOCKED_COLOR
+                    }),
+                    Node {
+                        margin: UiRect::all(Val::Px(6.0)),
+                        ..default()
+                    },
+                ));
+            }
+        });
+
+    commands.spawn((BackButton, button("Back", &assets), ChildOf(root)));
+}
+
+fn button_interaction(
+    back_button: Query<&Interaction, (Changed<Interaction>, With<BackButton>)>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if let Ok(&Interaction::Pressed) = back_button.single() {
+        game_state.set(GameState::Menu);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::Difficulty;
+use crate::GameAssets;
+use crate::GameMode;
+use crate::GameState;
+use crate::Players;
+use crate::despawn_screen;
+use crate::game::{CampaignStage, Challenge, DailyChallenge, DailyScores};
+use crate::ui::{button, text};
+
+use bevy::prelude::*;
+
+const MODIFIER_COLOR: Color = Color::srgb(1., 0.8, 0.2);
+
+pub fn daily_screen_plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameState::Daily), setup_daily)
+        .add_systems(
+            Update,
+            button_interaction.run_if(in_state(GameState::Daily)),
+        )
+        .add_systems(OnExit(GameState::Daily), despawn_screen::<OnDailyScreen>);
+}
+
+#[derive(Component)]
+struct OnDailyScreen;
+
+/// Holds the challenge shown, in case the day changes before it's played.
+#[derive(Component)]
+struct PlayButton(Challenge);
+
+#[derive(Component)]
+struct BackButton;
+
+fn setup_daily(mut commands: Commands, assets: Res<GameAssets>, scores: Res<DailyScores>) {
+    let challenge = Challenge::today();
+
+    let root = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::SpaceEvenly,
+                ..default()
+            },
+            OnDailyScreen,
+        ))
+        .id();
+
+    commands.spawn((
+        text(
+            format!("DAILY CHALLENGE\n{}", challenge.date()),
+            30.0,
+            &assets,
+        ),
+        ChildOf(root),
+    ));
+
+    commands.spawn((
+        text(challenge.modifier_names().join("\n"), 20.0, &assets),
+        TextColor(MODIFIER_COLOR),
+        ChildOf(root),
+    ));
+
+    // Today's leaderboard
+    let scores = scores.for_day(challenge.day);
+    let leaderboard = if scores.is_empty() {
+        "No scores yet today".to_string()
+    } else {
+        scores
+            .iter()
+            .enumerate()
+            .map(|(rank, score)| format!("{:>2}. {score:>6}", rank + 1))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    commands.spawn((text(leaderboard, 14.0, &assets), ChildOf(root)));
+
+    commands.spawn((
+        Node {
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        ChildOf(root),
+        children![
+            (PlayButton(challenge), button("Play", &assets)),
+            (BackButton, button("Back", &assets)),
+        ],
+    ));
+}
+
+/// Everyone plays the challenge alone, on the same difficulty and rules, so
+/// scores compare.
+#[allow(clippy::too_many_arguments)]
+fn button_interaction(
+    play_button: Query<(&Interaction, &PlayButton), Changed<Interaction>>,
+    back_button: Query<&Interaction, (Changed<Interaction>, With<BackButton>)>,
+    mut players: ResMut<Players>,
+    mut difficulty: ResMut<Difficulty>,
+    mut mode: ResMut<GameMode>,
+    mut stage: ResMut<CampaignStage>,
+    mut daily: ResMut<DailyChallenge>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if let Ok((&Interaction::Pressed, &PlayButton(challenge))) = play_button.single() {
+        *players = Players::One;
+        *difficulty = Difficulty::Normal;
+        *mode = GameMode::Standard;
+        *stage = CampaignStage(None);
+        *daily = DailyChallenge(Some(challenge));
+        game_state.set(GameState::Running);
+    }
+
+    if let Ok(&Interaction::Pressed) = back_button.single() {
+        game_state.set(GameState::Menu);
+    }
+}
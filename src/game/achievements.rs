@@ -0,0 +1,166 @@
+use crate::GameAssets;
+use crate::GameState;
+use crate::profile::{Achievement, Profile};
+
+use super::Combo;
+use super::Enemy;
+use super::EnemyKilled;
+use super::OnGameScreen;
+use super::RunStats;
+use super::WaveCleared;
+use super::combo::{MAX_MULTIPLIER, WaveStats, update_combo, wave_bonuses};
+use super::editor::Playtest;
+use super::stats::collect_stats;
+use super::versus::lockstep_ready;
+
+use bevy::prelude::*;
+
+const TOAST_TIME: f32 = 3.;
+
+const SHARPSHOOTER_ACCURACY: f32 = 0.75;
+/// Too few shots make accuracy meaningless.
+const SHARPSHOOTER_MIN_SHOTS: u32 = 20;
+const CENTURION_KILLS: u32 = 100;
+const SURVIVOR_TIME: f32 = 300.;
+
+pub fn achievements_plugin(app: &mut App) {
+    app.register_type::<Toasts>()
+        .register_type::<Toast>()
+        .add_systems(OnEnter(GameState::Running), setup_achievements)
+        .add_systems(
+            FixedUpdate,
+            check_achievements
+                .after(collect_stats)
+                .after(update_combo)
+                .before(wave_bonuses)
+                .run_if(
+                    in_state(GameState::Running)
+                        .and(lockstep_ready)
+                        .and(not(resource_exists::<Playtest>)),
+                ),
+        )
+        .add_systems(Update, update_toasts.run_if(in_state(GameState::Running)))
+        .add_systems(OnEnter(GameState::GameOver), record_run);
+}
+
+/// Column of unlock notifications in the corner of the screen.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct Toasts;
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct Toast(Timer);
+
+fn setup_achievements(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::FlexEnd,
+            position_type: PositionType::Absolute,
+            right: Val::Px(20.0),
+            top: Val::Px(20.0),
+            ..default()
+        },
+        Toasts,
+        OnGameScreen,
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn check_achievements(
+    mut commands: Commands,
+    mut profile: ResMut<Profile>,
+    stats: Res<RunStats>,
+    combo: Res<Combo>,
+    mut kill_reader: EventReader<EnemyKilled>,
+    mut wave_reader: EventReader<WaveCleared>,
+    wave_stats: Res<WaveStats>,
+    toasts_query: Query<Entity, With<Toasts>>,
+    assets: Res<GameAssets>,
+) {
+    let mut unlocked = Vec::new();
+
+    for EnemyKilled { enemy, .. } in kill_reader.read() {
+        unlocked.push(Achievement::FirstBlood);
+        if matches!(enemy, Enemy::Boss) {
+            unlocked.push(Achievement::BossSlayer);
+        }
+    }
+
+    // The wave bonuses start the next wave's numbers afterwards
+    if wave_reader.read().count() > 0 {
+        let shots = stats.shots.saturating_sub(wave_stats.shots_before);
+        let hits = stats.hits.saturating_sub(wave_stats.hits_before);
+        if !wave_stats.damaged {
+            unlocked.push(Achievement::Flawless);
+        }
+        if shots >= SHARPSHOOTER_MIN_SHOTS && hits as f32 >= shots as f32 * SHARPSHOOTER_ACCURACY {
+            unlocked.push(Achievement::Sharpshooter);
+        }
+    }
+
+    if combo.multiplier() >= MAX_MULTIPLIER {
+        unlocked.push(Achievement::ComboMaster);
+    }
+    if stats.kills.values().sum::<u32>() >= CENTURION_KILLS {
+        unlocked.push(Achievement::Centurion);
+    }
+    if stats.time_survived >= SURVIVOR_TIME {
+        unlocked.push(Achievement::Survivor);
+    }
+
+    let mut changed = false;
+    for achievement in unlocked {
+        if !profile.unlock(achievement) {
+            continue;
+        }
+        changed = true;
+
+        if let Ok(toasts) = toasts_query.single() {
+            commands.spawn((
+                Text::new(format!("Achievement unlocked: {}", achievement.name())),
+                TextFont {
+                    font: assets.font_press_start.clone(),
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1., 0.8, 0.2)),
+                Toast(Timer::from_seconds(TOAST_TIME, TimerMode::Once)),
+                ChildOf(toasts),
+            ));
+        }
+    }
+
+    // Save straight away, so quitting mid-run doesn't lose unlocks
+    if changed {
+        profile.save();
+    }
+}
+
+fn update_toasts(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut TextColor, &mut Toast)>,
+    time: Res<Time>,
+) {
+    for (entity, mut color, mut toast) in &mut query {
+        if toast.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        color
+            .0
+            .set_alpha(toast.0.fraction_remaining().min(0.25) * 4.);
+    }
+}
+
+fn record_run(mut profile: ResMut<Profile>, stats: Res<RunStats>) {
+    profile.runs_played += 1;
+    profile.total_kills += stats.kills.values().sum::<u32>();
+    if stats.shots >= SHARPSHOOTER_MIN_SHOTS {
+        profile.best_accuracy = profile.best_accuracy.max(stats.accuracy());
+    }
+    profile.longest_survival = profile.longest_survival.max(stats.time_survived);
+    profile.save();
+}
@@ -0,0 +1,251 @@
+use super::Boss;
+use super::Collider;
+use super::Direction;
+use super::Dive;
+use super::Enemy;
+use super::EnemyDirection;
+use super::FrontEnemies;
+use super::Health;
+use super::Hp;
+use super::Level;
+use super::MarchProgress;
+use super::OnGameScreen;
+use super::Position;
+use super::Score;
+use super::Shield;
+use super::Wave;
+use super::boss::spawn_boss;
+use super::collision;
+
+use std::collections::HashMap;
+
+use bevy::ecs::system::SystemParam;
+use bevy::math::bounding::Aabb2d;
+use bevy::prelude::*;
+
+pub const ENEMY_ROWS: usize = 15;
+pub const ENEMY_COLS: usize = 10;
+pub const ENEMY_SPACING: f32 = 50.;
+const ENEMY_SCALE: f32 = 20.;
+
+pub const SHIELD_SCALE: Vec3 = Vec3::new(30., 20., 0.);
+
+/// Everything that makes up one player's side of the game: the formation,
+/// the shields and the player's progress.
+#[derive(Clone)]
+pub struct Board {
+    /// Where each enemy is, with its health and the dive it's on
+    pub enemies: Vec<(Vec3, Position, Enemy, u32, Option<Dive>)>,
+    pub shields: Vec<(Vec3, Shield)>,
+    pub front_enemies: HashMap<usize, usize>,
+    pub direction: Direction,
+    /// Towards the next step, when the formation marches in steps
+    pub march_progress: f32,
+    pub score: u32,
+    pub hp: u8,
+    pub wave: u32,
+    /// Remaining health of the boss, if one is on the board
+    pub boss_hp: Option<u32>,
+}
+
+impl Board {
+    pub fn fresh(hp: u8, level: &Level) -> Self {
+        let (enemies, front_enemies) = formation(level);
+        let enemies = enemies
+            .into_iter()
+            .map(|(translation, position, enemy)| {
+                (translation, position, enemy, enemy.health(), None)
+            })
+            .collect();
+
+        let shields = level
+            .shields
+            .iter()
+            .map(|shield| (shield.extend(0.), Shield { hits: 0 }))
+            .collect();
+
+        Self {
+            enemies,
+            shields,
+            front_enemies,
+            direction: Direction::Right,
+            march_progress: 0.,
+            score: 0,
+            hp,
+            wave: 1,
+            boss_hp: None,
+        }
+    }
+
+    /// Spawns the formation and shields and inserts the board's resources.
+    pub fn spawn(self, commands: &mut Commands, level: &Level) {
+        for (translation, position, enemy, health, dive) in self.enemies {
+            let entity = spawn_enemy(commands, translation, position, enemy);
+            commands.entity(entity).insert(Health(health));
+            if let Some(dive) = dive {
+                commands.entity(entity).insert(dive);
+            }
+        }
+
+        for (translation, shield) in self.shields {
+            commands.spawn((
+                Transform {
+                    translation,
+                    scale: SHIELD_SCALE,
+                    ..default()
+                },
+                Collider(Aabb2d::new(
+                    translation.truncate(),
+                    SHIELD_SCALE.truncate() / 2.,
+                )),
+                collision::SHIELD,
+                Sprite {
+                    color: Color::srgb(0., 1., 0.5),
+                    ..default()
+                },
+                shield,
+                OnGameScreen,
+            ));
+        }
+
+        commands.insert_resource(FrontEnemies(self.front_enemies));
+        commands.insert_resource(EnemyDirection(self.direction));
+        commands.insert_resource(MarchProgress(self.march_progress));
+        commands.insert_resource(Score(self.score));
+        commands.insert_resource(Hp(self.hp));
+        commands.insert_resource(Wave(self.wave));
+        commands.insert_resource(level.march_style(self.wave));
+
+        if let Some(hp) = self.boss_hp {
+            spawn_boss(commands, hp, level.boss_hp, level.boss_style);
+        }
+    }
+}
+
+/// The parts of the world that make up the current board.
+#[derive(SystemParam)]
+pub struct CurrentBoard<'w, 's> {
+    #[allow(clippy::type_complexity)]
+    enemy_query: Query<
+        'w,
+        's,
+        (
+            &'static Transform,
+            &'static Position,
+            &'static Enemy,
+            &'static Health,
+            Option<&'static Dive>,
+        ),
+    >,
+    shield_query: Query<'w, 's, (&'static Transform, &'static Shield)>,
+    boss_query: Query<'w, 's, &'static Health, With<Boss>>,
+    front_enemies: Res<'w, FrontEnemies>,
+    direction: Res<'w, EnemyDirection>,
+    march_progress: Res<'w, MarchProgress>,
+    score: Res<'w, Score>,
+    hp: Res<'w, Hp>,
+    wave: Res<'w, Wave>,
+}
+
+impl CurrentBoard<'_, '_> {
+    pub fn capture(&self) -> Board {
+        Board {
+            enemies: self
+                .enemy_query
+                .iter()
+                .map(|(transform, &position, &enemy, health, dive)| {
+                    (
+                        transform.translation,
+                        position,
+                        enemy,
+                        health.0,
+                        dive.cloned(),
+                    )
+                })
+                .collect(),
+            shields: self
+                .shield_query
+                .iter()
+                .map(|(transform, &shield)| (transform.translation, shield))
+                .collect(),
+            front_enemies: self.front_enemies.0.clone(),
+            direction: self.direction.0,
+            march_progress: self.march_progress.0,
+            score: self.score.0,
+            hp: self.hp.0,
+            wave: self.wave.0,
+            boss_hp: self.boss_query.single().ok().map(|health| health.0),
+        }
+    }
+}
+
+/// The enemies of a fresh wave, along with the front row of every column.
+#[allow(clippy::type_complexity)]
+fn formation(level: &Level) -> (Vec<(Vec3, Position, Enemy)>, HashMap<usize, usize>) {
+    let mut enemies = Vec::new();
+    let mut front_enemies = HashMap::new();
+    for col in 0..ENEMY_COLS {
+        for row in 0..ENEMY_ROWS {
+            let Some(enemy) = level.grid[row][col] else {
+                continue;
+            };
+            let translation = Vec3::new(
+                col as f32 * ENEMY_SPACING - (ENEMY_COLS as f32 / 2.) * ENEMY_SPACING,
+                row as f32 * ENEMY_SPACING + 100.,
+                0.,
+            );
+            enemies.push((translation, Position { row, col }, enemy));
+            front_enemies.entry(col).or_insert(row);
+        }
+    }
+    (enemies, front_enemies)
+}
+
+pub fn enemy_color(enemy: Enemy) -> Color {
+    match enemy {
+        Enemy::Sniper => Color::srgb(1., 0.4, 0.8),
+        Enemy::Weaver => Color::srgb(0.3, 0.6, 1.),
+        Enemy::Seeker => Color::srgb(1., 0.8, 0.2),
+        Enemy::Lancer => Color::srgb(0.7, 0.3, 1.),
+        _ => Color::srgb(1., 0., 0.),
+    }
+}
+
+/// Starts a new wave with a fresh formation marching right.
+pub fn spawn_formation(commands: &mut Commands, level: &Level) {
+    let (enemies, front_enemies) = formation(level);
+    for (translation, position, enemy) in enemies {
+        spawn_enemy(commands, translation, position, enemy);
+    }
+
+    commands.insert_resource(FrontEnemies(front_enemies));
+    commands.insert_resource(EnemyDirection(Direction::Right));
+}
+
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    translation: Vec3,
+    position: Position,
+    enemy: Enemy,
+) -> Entity {
+    let scale = Vec3::splat(ENEMY_SCALE);
+    commands
+        .spawn((
+            Transform {
+                translation,
+                scale,
+                ..default()
+            },
+            position,
+            Health(enemy.health()),
+            Collider(Aabb2d::new(translation.truncate(), scale.truncate() / 2.)),
+            collision::ENEMY,
+            Sprite {
+                color: enemy_color(enemy),
+                ..default()
+            },
+            enemy,
+            OnGameScreen,
+        ))
+        .id()
+}
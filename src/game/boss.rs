@@ -0,0 +1,440 @@
+use crate::GameState;
+
+use super::BOTTOM_WALL;
+use super::Collider;
+use super::ENEMY_BULLET_SPEED;
+use super::Enemy;
+use super::Health;
+use super::Heavy;
+use super::Hp;
+use super::Invulnerable;
+use super::LEFT_WALL;
+use super::MyRng;
+use super::OnGameScreen;
+use super::Player;
+use super::PlayerHit;
+use super::RIGHT_WALL;
+use super::collision;
+use super::hit_player;
+use super::spawn_enemy_bullet;
+use super::versus::lockstep_ready;
+
+use bevy::math::bounding::{Aabb2d, IntersectsVolume};
+use bevy::prelude::*;
+
+use rand::prelude::*;
+
+const BOSS_START: Vec3 = Vec3::new(0., 200., 0.);
+const BOSS_SIZE: Vec2 = Vec2::new(160., 50.);
+const BOSS_SPEED: f32 = 80.;
+/// How far in from the side walls the boss turns around.
+const BOSS_MARGIN: f32 = 100.;
+
+const WEAK_POINT_SIZE: f32 = 18.;
+const WEAK_POINT_DAMAGE: u32 = 3;
+const WEAK_POINT_OFFSETS: [Vec2; 2] = [Vec2::new(-55., -30.), Vec2::new(55., -30.)];
+
+const BURST_BULLETS: usize = 5;
+const BURST_SPREAD: f32 = 0.15;
+
+const LASER_TIME: f32 = 2.5;
+/// Lasers flicker harmlessly for a moment before they start to hurt.
+const LASER_WARMUP: f32 = 0.6;
+const LASER_SWEEP_SPEED: f32 = 150.;
+const LASER_SIZE: Vec2 = Vec2::new(10., 520.);
+
+const MINIONS: usize = 3;
+const MINION_SIZE: f32 = 14.;
+const MINION_FALL_SPEED: f32 = 120.;
+const MINION_STEER_SPEED: f32 = 90.;
+
+pub fn boss_plugin(app: &mut App) {
+    app.register_type::<Boss>()
+        .register_type::<BossStyle>()
+        .register_type::<WeakPoint>()
+        .register_type::<Laser>()
+        .register_type::<Minion>()
+        .add_systems(
+            FixedUpdate,
+            (
+                boss_movement,
+                follow_boss.after(boss_movement),
+                boss_phase,
+                boss_attack,
+                laser_movement,
+                laser_collision,
+                minion_movement,
+            )
+                .run_if(in_state(GameState::Running).and(lockstep_ready)),
+        );
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Boss {
+    pub max_hp: u32,
+    style: BossStyle,
+    phase: usize,
+    direction: f32,
+    attack_timer: Timer,
+    next_attack: usize,
+}
+
+/// A separate hitbox on the boss that passes extra damage on to it.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct WeakPoint {
+    #[entities]
+    pub boss: Entity,
+    pub damage: u32,
+    offset: Vec2,
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Laser {
+    lifetime: Timer,
+    sweep: f32,
+}
+
+impl Laser {
+    fn active(&self) -> bool {
+        self.lifetime.elapsed_secs() >= LASER_WARMUP
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct Minion;
+
+#[derive(Clone, Copy)]
+enum BossAttack {
+    Burst,
+    Laser,
+    Minions,
+}
+
+/// Which boss a level ends with, picked with a `boss_style` line in its
+/// level file.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Reflect)]
+pub enum BossStyle {
+    /// Bursts first, lasers and minions once it's hurt
+    #[default]
+    Gunship,
+    /// Lasers from the start
+    Sentinel,
+    /// Sends minions from the start
+    Carrier,
+    /// A bit of everything, more of it each phase
+    Dreadnought,
+}
+
+impl BossStyle {
+    pub const ALL: [BossStyle; 4] = [
+        BossStyle::Gunship,
+        BossStyle::Sentinel,
+        BossStyle::Carrier,
+        BossStyle::Dreadnought,
+    ];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            BossStyle::Gunship => "gunship",
+            BossStyle::Sentinel => "sentinel",
+            BossStyle::Carrier => "carrier",
+            BossStyle::Dreadnought => "dreadnought",
+        }
+    }
+
+    /// The boss cycles through its phase's attacks in order.
+    fn attacks(self, phase: usize) -> &'static [BossAttack] {
+        use BossAttack::*;
+
+        let phases: [&[BossAttack]; 3] = match self {
+            BossStyle::Gunship => [&[Burst], &[Burst, Laser], &[Burst, Laser, Burst, Minions]],
+            BossStyle::Sentinel => [&[Laser], &[Laser, Burst], &[Laser, Laser, Burst]],
+            BossStyle::Carrier => [
+                &[Burst, Minions],
+                &[Minions, Burst, Minions],
+                &[Minions, Laser, Minions, Burst],
+            ],
+            BossStyle::Dreadnought => [
+                &[Burst, Laser],
+                &[Burst, Laser, Minions],
+                &[Laser, Burst, Minions, Burst, Laser],
+            ],
+        };
+        phases[phase]
+    }
+}
+
+const PHASE_ATTACK_INTERVALS: [f32; 3] = [1.6, 1.2, 0.9];
+const PHASE_COLORS: [Color; 3] = [
+    Color::srgb(0.6, 0.2, 0.9),
+    Color::srgb(1., 0.5, 0.1),
+    Color::srgb(1., 0.1, 0.1),
+];
+
+/// Phases change as the boss drops below two thirds and one third health.
+fn phase_for(hp: u32, max_hp: u32) -> usize {
+    if hp * 3 > max_hp * 2 {
+        0
+    } else if hp * 3 > max_hp {
+        1
+    } else {
+        2
+    }
+}
+
+/// `hp` is below `max_hp` when a boss fight is resumed.
+pub fn spawn_boss(commands: &mut Commands, hp: u32, max_hp: u32, style: BossStyle) {
+    let phase = phase_for(hp, max_hp);
+
+    let boss = commands
+        .spawn((
+            Transform {
+                translation: BOSS_START,
+                scale: BOSS_SIZE.extend(1.),
+                ..default()
+            },
+            Sprite {
+                color: PHASE_COLORS[phase],
+                ..default()
+            },
+            Collider(Aabb2d::new(BOSS_START.truncate(), BOSS_SIZE / 2.)),
+            collision::ENEMY,
+            Health(hp),
+            Enemy::Boss,
+            Boss {
+                max_hp,
+                style,
+                phase,
+                direction: 1.,
+                attack_timer: Timer::from_seconds(
+                    PHASE_ATTACK_INTERVALS[phase],
+                    TimerMode::Repeating,
+                ),
+                next_attack: 0,
+            },
+            OnGameScreen,
+        ))
+        .id();
+
+    for offset in WEAK_POINT_OFFSETS {
+        let translation = BOSS_START + offset.extend(1.);
+        commands.spawn((
+            Transform {
+                translation,
+                scale: Vec3::splat(WEAK_POINT_SIZE),
+                ..default()
+            },
+            Sprite {
+                color: Color::srgb(1., 0.9, 0.2),
+                ..default()
+            },
+            Collider(Aabb2d::new(
+                translation.truncate(),
+                Vec2::splat(WEAK_POINT_SIZE / 2.),
+            )),
+            collision::ENEMY,
+            WeakPoint {
+                boss,
+                damage: WEAK_POINT_DAMAGE,
+                offset,
+            },
+            OnGameScreen,
+        ));
+    }
+}
+
+fn boss_movement(mut query: Query<(&mut Transform, &mut Boss)>, time: Res<Time<Fixed>>) {
+    for (mut transform, mut boss) in &mut query {
+        let speed = BOSS_SPEED * (boss.phase + 1) as f32;
+        transform.translation.x += boss.direction * speed * time.delta_secs();
+
+        if transform.translation.x > RIGHT_WALL - BOSS_MARGIN {
+            boss.direction = -1.;
+        } else if transform.translation.x < LEFT_WALL + BOSS_MARGIN {
+            boss.direction = 1.;
+        }
+    }
+}
+
+fn follow_boss(
+    mut commands: Commands,
+    mut weak_point_query: Query<(Entity, &mut Transform, &WeakPoint)>,
+    boss_query: Query<&Transform, (With<Boss>, Without<WeakPoint>)>,
+) {
+    for (entity, mut transform, weak_point) in &mut weak_point_query {
+        match boss_query.get(weak_point.boss) {
+            Ok(boss_transform) => {
+                transform.translation = boss_transform.translation + weak_point.offset.extend(1.);
+            }
+            Err(_) => {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+fn boss_phase(mut query: Query<(&mut Boss, &Health, &mut Sprite)>) {
+    for (mut boss, &Health(hp), mut sprite) in &mut query {
+        let phase = phase_for(hp, boss.max_hp);
+        if phase == boss.phase {
+            continue;
+        }
+
+        boss.phase = phase;
+        boss.next_attack = 0;
+        boss.attack_timer =
+            Timer::from_seconds(PHASE_ATTACK_INTERVALS[phase], TimerMode::Repeating);
+        sprite.color = PHASE_COLORS[phase];
+    }
+}
+
+fn boss_attack(
+    mut commands: Commands,
+    mut boss_query: Query<(&Transform, &mut Boss)>,
+    player_query: Query<&Transform, (With<Player>, Without<Boss>)>,
+    time: Res<Time<Fixed>>,
+    mut rng: ResMut<MyRng>,
+) {
+    let target = player_query
+        .single()
+        .map_or(Vec3::new(0., BOTTOM_WALL, 0.), |transform| {
+            transform.translation
+        });
+
+    for (transform, mut boss) in &mut boss_query {
+        if !boss.attack_timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        let attacks = boss.style.attacks(boss.phase);
+        let attack = attacks[boss.next_attack % attacks.len()];
+        boss.next_attack += 1;
+
+        let origin = transform.translation - Vec3::new(0., BOSS_SIZE.y / 2., 0.);
+        match attack {
+            BossAttack::Burst => {
+                let aim = (target - origin).truncate().normalize_or(Vec2::NEG_Y);
+                for i in 0..BURST_BULLETS {
+                    let angle = (i as f32 - (BURST_BULLETS - 1) as f32 / 2.) * BURST_SPREAD;
+                    let velocity = Vec2::from_angle(angle).rotate(aim) * ENEMY_BULLET_SPEED * 0.7;
+                    let bullet = spawn_enemy_bullet(&mut commands, origin, velocity);
+                    commands.entity(bullet).insert(Heavy);
+                }
+            }
+            BossAttack::Laser => {
+                let translation = origin - Vec3::new(0., LASER_SIZE.y / 2., 0.);
+                commands.spawn((
+                    Transform {
+                        translation,
+                        scale: LASER_SIZE.extend(1.),
+                        ..default()
+                    },
+                    Sprite {
+                        color: Color::srgba(1., 0.2, 0.6, 0.3),
+                        ..default()
+                    },
+                    Collider(Aabb2d::new(translation.truncate(), LASER_SIZE / 2.)),
+                    Laser {
+                        lifetime: Timer::from_seconds(LASER_TIME, TimerMode::Once),
+                        // Sweep across towards the player
+                        sweep: (target.x - origin.x).signum() * LASER_SWEEP_SPEED,
+                    },
+                    OnGameScreen,
+                ));
+            }
+            BossAttack::Minions => {
+                for _ in 0..MINIONS {
+                    let translation = origin + Vec3::new(rng.0.random_range(-60. ..60.), 0., 0.);
+                    commands.spawn((
+                        Transform {
+                            translation,
+                            scale: Vec3::splat(MINION_SIZE),
+                            ..default()
+                        },
+                        Sprite {
+                            color: Color::srgb(1., 0.6, 0.2),
+                            ..default()
+                        },
+                        Collider(Aabb2d::new(
+                            translation.truncate(),
+                            Vec2::splat(MINION_SIZE / 2.),
+                        )),
+                        collision::ENEMY,
+                        Health(Enemy::Minion.health()),
+                        Enemy::Minion,
+                        Minion,
+                        OnGameScreen,
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn laser_movement(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Transform, &mut Sprite, &mut Laser)>,
+    time: Res<Time<Fixed>>,
+) {
+    for (entity, mut transform, mut sprite, mut laser) in &mut query {
+        if laser.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        if laser.active() {
+            transform.translation.x += laser.sweep * time.delta_secs();
+            sprite.color.set_alpha(1.);
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn laser_collision(
+    mut commands: Commands,
+    laser_query: Query<(&Collider, &Laser)>,
+    player_query: Query<(Entity, &Transform, &Collider), (With<Player>, Without<Invulnerable>)>,
+    mut hp: ResMut<Hp>,
+    mut event_writer: EventWriter<PlayerHit>,
+) {
+    if let Ok((player_entity, transform, Collider(player_aabb))) = player_query.single()
+        && laser_query.iter().any(|(Collider(laser_aabb), laser)| {
+            laser.active() && laser_aabb.intersects(player_aabb)
+        })
+    {
+        hit_player(
+            &mut commands,
+            player_entity,
+            transform.translation,
+            &mut hp,
+            &mut event_writer,
+        );
+    }
+}
+
+fn minion_movement(
+    mut commands: Commands,
+    mut minion_query: Query<(Entity, &mut Transform), With<Minion>>,
+    player_query: Query<&Transform, (With<Player>, Without<Minion>)>,
+    time: Res<Time<Fixed>>,
+) {
+    let target_x = player_query
+        .single()
+        .map(|transform| transform.translation.x);
+
+    for (entity, mut transform) in &mut minion_query {
+        transform.translation.y -= MINION_FALL_SPEED * time.delta_secs();
+        if let Ok(target_x) = target_x {
+            let steer = (target_x - transform.translation.x).clamp(-1., 1.);
+            transform.translation.x += steer * MINION_STEER_SPEED * time.delta_secs();
+        }
+
+        if transform.translation.y < BOTTOM_WALL {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use bevy::math::IVec2;
+use bevy::math::bounding::{Aabb2d, IntersectsVolume};
+
+/// Uniform grid of bounding boxes. Boxes are stored in every cell they
+/// overlap, so lookups only test the boxes in nearby cells.
+pub struct Grid<T> {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(Aabb2d, T)>>,
+}
+
+impl<T: Copy + Ord> Grid<T> {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Empties every cell while keeping their allocations for the next rebuild.
+    pub fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+    }
+
+    pub fn insert(&mut self, aabb: Aabb2d, item: T) {
+        for cell in self.cells_overlapping(&aabb) {
+            self.cells.entry(cell).or_default().push((aabb, item));
+        }
+    }
+
+    /// Collects every item whose box intersects `aabb` into `out`, sorted and
+    /// without duplicates.
+    pub fn query(&self, aabb: &Aabb2d, out: &mut Vec<T>) {
+        out.clear();
+        for cell in self.cells_overlapping(aabb) {
+            if let Some(items) = self.cells.get(&cell) {
+                out.extend(
+                    items
+                        .iter()
+                        .filter(|(item_aabb, _)| item_aabb.intersects(aabb))
+                        .map(|&(_, item)| item),
+                );
+            }
+        }
+        out.sort_unstable();
+        out.dedup();
+    }
+
+    fn cells_overlapping(&self, aabb: &Aabb2d) -> impl Iterator<Item = IVec2> + use<T> {
+        let min = (aabb.min / self.cell_size).floor().as_ivec2();
+        let max = (aabb.max / self.cell_size).floor().as_ivec2();
+        (min.x..=max.x).flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bevy::math::Vec2;
+
+    fn square(x: f32, y: f32, half_size: f32) -> Aabb2d {
+        Aabb2d::new(Vec2::new(x, y), Vec2::splat(half_size))
+    }
+
+    #[test]
+    fn finds_only_intersecting_items() {
+        let mut grid = Grid::new(10.);
+        grid.insert(square(0., 0., 2.), 1);
+        grid.insert(square(5., 5., 2.), 2);
+        grid.insert(square(50., 50., 2.), 3);
+
+        let mut out = Vec::new();
+        grid.query(&square(1., 1., 1.), &mut out);
+        assert_eq!(out, [1]);
+
+        grid.query(&square(3., 3., 2.), &mut out);
+        assert_eq!(out, [1, 2]);
+
+        grid.query(&square(-40., 20., 2.), &mut out);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn items_across_cells_come_back_once() {
+        let mut grid = Grid::new(10.);
+        // Spans nine cells, including negative ones
+        grid.insert(square(0., 0., 12.), 7);
+
+        let mut out = Vec::new();
+        grid.query(&square(0., 0., 15.), &mut out);
+        assert_eq!(out, [7]);
+
+        grid.query(&square(-11., 11., 1.), &mut out);
+        assert_eq!(out, [7]);
+    }
+
+    #[test]
+    fn clear_empties_the_grid() {
+        let mut grid = Grid::new(10.);
+        grid.insert(square(0., 0., 2.), 1);
+        grid.clear();
+
+        let mut out = vec![9];
+        grid.query(&square(0., 0., 2.), &mut out);
+        assert!(out.is_empty());
+
+        grid.insert(square(0., 0., 2.), 2);
+        grid.query(&square(0., 0., 2.), &mut out);
+        assert_eq!(out, [2]);
+    }
+}
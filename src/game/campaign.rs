@@ -0,0 +1,62 @@
+//! The campaign: stages played in order, each a level of its own that ends
+//! with its boss. Clearing a stage unlocks the next one.
+
+use crate::GameState;
+use crate::profile::Profile;
+
+use super::RunStats;
+use super::Score;
+use super::stats::Outcome;
+
+use bevy::prelude::*;
+
+pub fn campaign_plugin(app: &mut App) {
+    app.init_resource::<CampaignStage>()
+        .add_systems(
+            Update,
+            finish_stage.run_if(in_state(GameState::Running).and(resource_added::<StageClear>)),
+        )
+        .add_systems(OnEnter(GameState::GameOver), record_stage_score)
+        .add_systems(OnExit(GameState::Running), remove_stage_clear);
+}
+
+/// The campaign stage being played, `None` for endless runs.
+#[derive(Resource, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct CampaignStage(pub Option<usize>);
+
+/// Inserted once the stage's boss is beaten, so no more waves come in.
+#[derive(Resource)]
+pub struct StageClear;
+
+/// Beating the boss ends the run, which is then recorded like any other.
+fn finish_stage(
+    score: Res<Score>,
+    mut stats: ResMut<RunStats>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    stats.score = score.0;
+    stats.outcome = Outcome::StageClear;
+    game_state.set(GameState::GameOver);
+}
+
+/// A stage lost still counts towards its best score, and a stage cleared
+/// also unlocks the next one.
+fn record_stage_score(
+    stage: Res<CampaignStage>,
+    stats: Res<RunStats>,
+    mut profile: ResMut<Profile>,
+) {
+    let Some(stage) = stage.0 else {
+        return;
+    };
+    if stats.outcome == Outcome::StageClear {
+        profile.clear_stage(stage, stats.difficulty, stats.score);
+    } else {
+        profile.record_stage_score(stage, stats.difficulty, stats.score);
+    }
+    profile.save();
+}
+
+fn remove_stage_clear(mut commands: Commands) {
+    commands.remove_resource::<StageClear>();
+}
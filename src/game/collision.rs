@@ -0,0 +1,224 @@
+use super::Broadphase;
+use super::Collider;
+use super::Heavy;
+use super::Invulnerable;
+use super::PreviousPosition;
+use super::sweep::sweep;
+
+use bevy::math::bounding::BoundingVolume;
+use bevy::prelude::*;
+
+/// What kind of thing a collider is, for deciding what it can hit.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Reflect)]
+pub enum Layer {
+    Player,
+    PlayerBullet,
+    EnemyBullet,
+    Enemy,
+    Shield,
+    Pickup,
+}
+
+#[derive(Component, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct CollisionLayers {
+    pub layer: Layer,
+    mask: u8,
+}
+
+impl CollisionLayers {
+    const fn new(layer: Layer, collides_with: &[Layer]) -> Self {
+        let mut mask = 0;
+        let mut i = 0;
+        while i < collides_with.len() {
+            mask |= 1 << collides_with[i] as u8;
+            i += 1;
+        }
+        Self { layer, mask }
+    }
+
+    fn collides_with(&self, layer: Layer) -> bool {
+        self.mask & 1 << layer as u8 != 0
+    }
+}
+
+pub const PLAYER: CollisionLayers =
+    CollisionLayers::new(Layer::Player, &[Layer::EnemyBullet, Layer::Pickup]);
+/// Only player bullets look for enemy bullets, so a pair that meets is
+/// reported once rather than from both sides.
+pub const PLAYER_BULLET: CollisionLayers = CollisionLayers::new(
+    Layer::PlayerBullet,
+    &[Layer::Enemy, Layer::Shield, Layer::EnemyBullet],
+);
+pub const ENEMY_BULLET: CollisionLayers =
+    CollisionLayers::new(Layer::EnemyBullet, &[Layer::Player, Layer::Shield]);
+pub const ENEMY: CollisionLayers = CollisionLayers::new(Layer::Enemy, &[Layer::PlayerBullet]);
+pub const SHIELD: CollisionLayers =
+    CollisionLayers::new(Layer::Shield, &[Layer::PlayerBullet, Layer::EnemyBullet]);
+
+/// `entity` ran into `other` this tick. Only moving entities like bullets
+/// report collisions, and only with the first thing along their path, so
+/// each of them is resolved exactly once. An enemy bullet shot down this tick
+/// reports nothing of its own.
+#[derive(Event, Clone, Copy)]
+pub struct Collision {
+    pub entity: Entity,
+    pub other: Entity,
+    /// The layer of `other`
+    pub layer: Layer,
+}
+
+#[allow(clippy::type_complexity)]
+pub fn detect_collisions(
+    broadphase: Res<Broadphase>,
+    mover_query: Query<(Entity, &Collider, &CollisionLayers, &PreviousPosition)>,
+    // Invulnerable players let bullets pass straight through
+    target_query: Query<
+        (
+            &Collider,
+            &CollisionLayers,
+            Option<&PreviousPosition>,
+            Has<Heavy>,
+        ),
+        Without<Invulnerable>,
+    >,
+    mut event_writer: EventWriter<Collision>,
+) {
+    let mut candidates = Vec::new();
+    let mut hits = Vec::new();
+    for (entity, Collider(aabb), layers, previous) in &mover_query {
+        let (start, displacement) = previous.sweep_start(aabb);
+        broadphase.0.query(&start.merge(aabb), &mut candidates);
+
+        let hit = candidates
+            .iter()
+            .filter(|&&other| other != entity)
+            .filter_map(|&other| {
+                let (Collider(other_aabb), other_layers, other_previous, heavy) =
+                    target_query.get(other).ok()?;
+                if !layers.collides_with(other_layers.layer) {
+                    return None;
+                }
+
+                // Against another moving entity, sweep along the relative motion
+                let time = match other_previous {
+                    Some(other_previous) => {
+                        let (other_start, other_displacement) =
+                            other_previous.sweep_start(other_aabb);
+                        sweep(&start, displacement - other_displacement, &other_start)?
+                    }
+                    None => sweep(&start, displacement, other_aabb)?,
+                };
+                Some((time, other, other_layers.layer, heavy))
+            })
+            .min_by(|(a, ..), (b, ..)| a.total_cmp(b));
+
+        if let Some((_, other, layer, heavy)) = hit {
+            hits.push((entity, other, layer, heavy));
+        }
+    }
+
+    // Heavy bullets can't be shot down, and keep going
+    let intercepted: Vec<Entity> = hits
+        .iter()
+        .filter(|&&(_, _, layer, heavy)| layer == Layer::EnemyBullet && !heavy)
+        .map(|&(_, other, ..)| other)
+        .collect();
+    for (entity, other, layer, _) in hits {
+        if !intercepted.contains(&entity) {
+            event_writer.write(Collision {
+                entity,
+                other,
+                layer,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::rebuild_broadphase;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::math::bounding::Aabb2d;
+
+    fn spawn(
+        world: &mut World,
+        center: Vec2,
+        from: Option<Vec2>,
+        layers: CollisionLayers,
+    ) -> Entity {
+        let mut entity = world.spawn((Collider(Aabb2d::new(center, Vec2::splat(2.))), layers));
+        if let Some(from) = from {
+            entity.insert(PreviousPosition(from));
+        }
+        entity.id()
+    }
+
+    fn collisions(world: &mut World) -> Vec<(Entity, Entity, Layer)> {
+        world.run_system_once(rebuild_broadphase).unwrap();
+        world.run_system_once(detect_collisions).unwrap();
+        world
+            .resource::<Events<Collision>>()
+            .iter_current_update_events()
+            .map(|collision| (collision.entity, collision.other, collision.layer))
+            .collect()
+    }
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<Broadphase>();
+        world.init_resource::<Events<Collision>>();
+        world
+    }
+
+    #[test]
+    fn intercepted_bullet_does_not_hit_the_player() {
+        let mut world = world();
+        let player = spawn(&mut world, Vec2::ZERO, None, PLAYER);
+        let enemy_bullet = spawn(
+            &mut world,
+            Vec2::ZERO,
+            Some(Vec2::new(0., 10.)),
+            ENEMY_BULLET,
+        );
+        let player_bullet = spawn(
+            &mut world,
+            Vec2::new(0., 5.),
+            Some(Vec2::new(0., -5.)),
+            PLAYER_BULLET,
+        );
+
+        let collisions = collisions(&mut world);
+        assert_eq!(
+            collisions,
+            [(player_bullet, enemy_bullet, Layer::EnemyBullet)]
+        );
+        assert!(collisions.iter().all(|&(_, other, _)| other != player));
+    }
+
+    #[test]
+    fn heavy_bullet_still_hits_the_player() {
+        let mut world = world();
+        let player = spawn(&mut world, Vec2::ZERO, None, PLAYER);
+        let enemy_bullet = spawn(
+            &mut world,
+            Vec2::ZERO,
+            Some(Vec2::new(0., 10.)),
+            ENEMY_BULLET,
+        );
+        world.entity_mut(enemy_bullet).insert(Heavy);
+        let player_bullet = spawn(
+            &mut world,
+            Vec2::new(0., 5.),
+            Some(Vec2::new(0., -5.)),
+            PLAYER_BULLET,
+        );
+
+        let collisions = collisions(&mut world);
+        assert_eq!(collisions.len(), 2);
+        assert!(collisions.contains(&(player_bullet, enemy_bullet, Layer::EnemyBullet)));
+        assert!(collisions.contains(&(enemy_bullet, player, Layer::Player)));
+    }
+}
@@ -0,0 +1,233 @@
+use crate::GameState;
+
+use super::Bullet;
+use super::Collision;
+use super::EnemyKilled;
+use super::Hp;
+use super::Layer;
+use super::PlayerHit;
+use super::Position;
+use super::Preset;
+use super::RunStats;
+use super::Score;
+use super::ShotMissed;
+use super::WaveCleared;
+use super::stats::collect_stats;
+use super::update_score;
+use super::versus::lockstep_ready;
+
+use std::collections::BTreeSet;
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+/// How long the next kill has to keep a combo going.
+const COMBO_WINDOW: f32 = 1.5;
+const KILLS_PER_MULTIPLIER: u32 = 4;
+pub const MAX_MULTIPLIER: u32 = 8;
+
+const ROW_CLEAR_BONUS: u32 = 100;
+const COLUMN_CLEAR_BONUS: u32 = 150;
+/// Scaled by the wave's accuracy.
+const ACCURACY_BONUS: u32 = 500;
+const NO_DAMAGE_BONUS: u32 = 300;
+
+pub fn combo_plugin(app: &mut App) {
+    app.register_type::<Combo>()
+        .register_type::<WaveStats>()
+        .add_systems(OnEnter(GameState::Running), setup_combo)
+        .add_systems(
+            FixedUpdate,
+            (update_combo, clear_bonuses, wave_bonuses)
+                .chain()
+                .after(update_score)
+                .after(collect_stats)
+                .run_if(in_state(GameState::Running).and(lockstep_ready)),
+        );
+}
+
+/// Kills in quick succession raise the score multiplier, until the window
+/// runs out, a shot misses or the player is hit.
+#[derive(Resource, Reflect, Clone)]
+#[reflect(Resource)]
+pub struct Combo {
+    pub chain: u32,
+    pub window: Timer,
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        Self {
+            chain: 0,
+            window: Timer::from_seconds(COMBO_WINDOW, TimerMode::Once),
+        }
+    }
+}
+
+impl Combo {
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.chain / KILLS_PER_MULTIPLIER).min(MAX_MULTIPLIER)
+    }
+
+    fn reset(&mut self) {
+        self.chain = 0;
+    }
+}
+
+/// How the player is doing in the current wave, for the wave-end bonuses.
+/// Shots and hits are counted by [`RunStats`], this only remembers where
+/// they stood when the wave began.
+#[derive(Resource, Reflect, Default, Clone)]
+#[reflect(Resource)]
+pub struct WaveStats {
+    pub shots_before: u32,
+    pub hits_before: u32,
+    pub damaged: bool,
+}
+
+pub fn setup_combo(mut commands: Commands) {
+    commands.insert_resource(Combo::default());
+    commands.insert_resource(WaveStats::default());
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_combo(
+    mut combo: ResMut<Combo>,
+    mut stats: ResMut<WaveStats>,
+    bullet_query: Query<&Bullet>,
+    mut kill_reader: EventReader<EnemyKilled>,
+    mut collision_reader: EventReader<Collision>,
+    mut miss_reader: EventReader<ShotMissed>,
+    mut hit_reader: EventReader<PlayerHit>,
+    time: Res<Time<Fixed>>,
+) {
+    let kills = kill_reader.read().count() as u32;
+    if kills > 0 {
+        combo.chain += kills;
+        combo.window.reset();
+    } else if combo.window.tick(time.delta()).just_finished() {
+        combo.reset();
+    }
+
+    // Shooting the shields counts as a miss
+    if collision_reader.read().any(|collision| {
+        collision.layer == Layer::Shield
+            && matches!(bullet_query.get(collision.entity), Ok(Bullet::Player))
+    }) {
+        combo.reset();
+    }
+
+    if miss_reader.read().count() > 0 {
+        combo.reset();
+    }
+
+    if hit_reader.read().count() > 0 {
+        combo.reset();
+        stats.damaged = true;
+    }
+}
+
+/// Bonuses for wiping out a whole row or column of the formation, once
+/// however many of its enemies went down together.
+fn clear_bonuses(
+    mut kill_reader: EventReader<EnemyKilled>,
+    query: Query<&Position>,
+    mut score: ResMut<Score>,
+    mut hp: ResMut<Hp>,
+    preset: Res<Preset>,
+) {
+    let killed: HashSet<_> = kill_reader
+        .read()
+        .filter_map(|kill| kill.position)
+        .map(|Position { row, col }| (row, col))
+        .collect();
+    if killed.is_empty() {
+        return;
+    }
+
+    // Enemies killed this tick may still be around
+    let left: Vec<_> = query
+        .iter()
+        .filter(|p| !killed.contains(&(p.row, p.col)))
+        .collect();
+
+    let rows: BTreeSet<_> = killed.iter().map(|&(row, _)| row).collect();
+    for row in rows {
+        if !left.iter().any(|p| p.row == row) {
+            score.add(preset.points(ROW_CLEAR_BONUS), &mut hp, &preset);
+        }
+    }
+    let cols: BTreeSet<_> = killed.iter().map(|&(_, col)| col).collect();
+    for col in cols {
+        if !left.iter().any(|p| p.col == col) {
+            score.add(preset.points(COLUMN_CLEAR_BONUS), &mut hp, &preset);
+        }
+    }
+}
+
+pub fn wave_bonuses(
+    mut wave_reader: EventReader<WaveCleared>,
+    mut stats: ResMut<WaveStats>,
+    run: Res<RunStats>,
+    mut score: ResMut<Score>,
+    mut hp: ResMut<Hp>,
+    preset: Res<Preset>,
+) {
+    for _ in wave_reader.read() {
+        let shots = run.shots.saturating_sub(stats.shots_before);
+        let hits = run.hits.saturating_sub(stats.hits_before);
+        if shots > 0 {
+            let accuracy = (hits as f32 / shots as f32).min(1.);
+            let bonus = (ACCURACY_BONUS as f32 * accuracy).round() as u32;
+            score.add(preset.points(bonus), &mut hp, &preset);
+        }
+        if !stats.damaged {
+            score.add(preset.points(NO_DAMAGE_BONUS), &mut hp, &preset);
+        }
+
+        *stats = WaveStats {
+            shots_before: run.shots,
+            hits_before: run.hits,
+            damaged: false,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Difficulty;
+    use crate::GameMode;
+    use crate::game::Enemy;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    fn kill(world: &mut World, row: usize, col: usize) {
+        world.send_event(EnemyKilled {
+            position: Some(Position { row, col }),
+            translation: Vec3::ZERO,
+            enemy: Enemy::Normal,
+        });
+    }
+
+    #[test]
+    fn enemies_killed_together_clear_their_row() {
+        let mut world = World::new();
+        world.init_resource::<Events<EnemyKilled>>();
+        world.insert_resource(Score(0));
+        world.insert_resource(Hp(3));
+        let preset = Preset::new(Difficulty::Normal, GameMode::Standard);
+        world.insert_resource(preset);
+
+        // The last two of row 0, with row 1 still holding column 0
+        world.spawn(Position { row: 0, col: 0 });
+        world.spawn(Position { row: 0, col: 1 });
+        world.spawn(Position { row: 1, col: 0 });
+        kill(&mut world, 0, 0);
+        kill(&mut world, 0, 1);
+        world.run_system_once(clear_bonuses).unwrap();
+
+        let expected = preset.points(ROW_CLEAR_BONUS) + preset.points(COLUMN_CLEAR_BONUS);
+        assert_eq!(world.resource::<Score>().0, expected);
+    }
+}
@@ -0,0 +1,253 @@
+//! The daily challenge: a seed and a set of modifiers worked out from the
+//! date alone, played on the classic formation, so everyone gets the same run
+//! on the same day without a network. Scores go on a local leaderboard for
+//! each day.
+
+use crate::GameState;
+use crate::data_path;
+use crate::write_data_file;
+
+use super::RunStats;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+
+const SCORES_FILE: &str = "daily.txt";
+/// Scores kept on each day's leaderboard.
+const LEADERBOARD_SIZE: usize = 10;
+const SECONDS_PER_DAY: u64 = 86_400;
+
+pub fn daily_plugin(app: &mut App) {
+    app.init_resource::<DailyChallenge>()
+        .insert_resource(DailyScores::load())
+        .add_systems(OnEnter(GameState::GameOver), record_daily_score);
+}
+
+/// The daily challenge being played, `None` for any other run.
+#[derive(Resource, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct DailyChallenge(pub Option<Challenge>);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Challenge {
+    /// Days since the Unix epoch, in UTC so the day changes at the same
+    /// moment everywhere
+    pub day: u64,
+    pub seed: u64,
+    pub modifiers: Modifiers,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Modifiers {
+    pub no_shields: bool,
+    /// The formation marches twice as fast
+    pub fast_enemies: bool,
+    /// Left moves right and right moves left
+    pub mirrored: bool,
+}
+
+impl Challenge {
+    pub fn today() -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Self::for_day(now.as_secs() / SECONDS_PER_DAY)
+    }
+
+    pub fn for_day(day: u64) -> Self {
+        let seed = splitmix64(day);
+        let mut modifiers = Modifiers {
+            no_shields: seed & 1 != 0,
+            fast_enemies: seed & 2 != 0,
+            mirrored: seed & 4 != 0,
+        };
+        // Every day has at least one
+        if modifiers == Modifiers::default() {
+            match (seed >> 3) % 3 {
+                0 => modifiers.no_shields = true,
+                1 => modifiers.fast_enemies = true,
+                _ => modifiers.mirrored = true,
+            }
+        }
+        Self {
+            day,
+            seed,
+            modifiers,
+        }
+    }
+
+    /// The day as `YYYY-MM-DD`.
+    pub fn date(&self) -> String {
+        // Howard Hinnant's days-to-civil algorithm, shifted so years start
+        // in March and leap days fall at the end
+        let days = self.day + 719_468;
+        let era = days / 146_097;
+        let day_of_era = days % 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        };
+        let year = year_of_era + era * 400 + u64::from(month <= 2);
+        format!("{year:04}-{month:02}-{day:02}")
+    }
+
+    pub fn modifier_names(&self) -> Vec<&'static str> {
+        let Modifiers {
+            no_shields,
+            fast_enemies,
+            mirrored,
+        } = self.modifiers;
+        [
+            (no_shields, "No shields"),
+            (fast_enemies, "Double enemy speed"),
+            (mirrored, "Mirrored controls"),
+        ]
+        .into_iter()
+        .filter_map(|(on, name)| on.then_some(name))
+        .collect()
+    }
+}
+
+/// Spreads consecutive days over the whole range of seeds.
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Best scores of every daily challenge played, kept in a text file of
+/// `day score` lines in the data directory.
+#[derive(Resource, Default)]
+pub struct DailyScores(BTreeMap<u64, Vec<u32>>);
+
+impl DailyScores {
+    fn load() -> Self {
+        let contents = match fs::read_to_string(data_path(SCORES_FILE)) {
+            Ok(contents) => contents,
+            Err(err) => {
+                if err.kind() != io::ErrorKind::NotFound {
+                    warn!("Failed to read daily scores: {err}");
+                }
+                return Self::default();
+            }
+        };
+        Self::parse(&contents)
+    }
+
+    /// Malformed lines are skipped.
+    fn parse(contents: &str) -> Self {
+        let mut scores = Self::default();
+        for line in contents.lines() {
+            if let Some((day, score)) = line.split_once(' ')
+                && let (Ok(day), Ok(score)) = (day.parse(), score.trim().parse())
+            {
+                scores.record(day, score);
+            }
+        }
+        scores
+    }
+
+    fn contents(&self) -> String {
+        let mut contents = String::new();
+        for (day, scores) in &self.0 {
+            for score in scores {
+                contents += &format!("{day} {score}\n");
+            }
+        }
+        contents
+    }
+
+    fn save(&self) {
+        if let Err(err) = write_data_file(SCORES_FILE, self.contents()) {
+            warn!("Failed to save daily scores: {err}");
+        }
+    }
+
+    fn record(&mut self, day: u64, score: u32) {
+        let scores = self.0.entry(day).or_default();
+        scores.push(score);
+        scores.sort_unstable_by(|a, b| b.cmp(a));
+        scores.truncate(LEADERBOARD_SIZE);
+    }
+
+    /// Highest first.
+    pub fn for_day(&self, day: u64) -> &[u32] {
+        self.0.get(&day).map_or(&[], Vec::as_slice)
+    }
+}
+
+fn record_daily_score(
+    daily: Res<DailyChallenge>,
+    stats: Res<RunStats>,
+    mut scores: ResMut<DailyScores>,
+) {
+    if let Some(challenge) = daily.0 {
+        scores.record(challenge.day, stats.score);
+        scores.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        let date = |day| Challenge::for_day(day).date();
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(11_016), "2000-02-29");
+        assert_eq!(date(20_088), "2024-12-31");
+        assert_eq!(date(47_541), "2100-03-01");
+    }
+
+    #[test]
+    fn every_day_has_a_modifier() {
+        for day in 0..1000 {
+            let challenge = Challenge::for_day(day);
+            assert_eq!(challenge, Challenge::for_day(day));
+            assert!(!challenge.modifier_names().is_empty());
+        }
+        assert_ne!(Challenge::for_day(1).seed, Challenge::for_day(2).seed);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut scores = DailyScores::default();
+        scores.record(20_000, 300);
+        scores.record(20_000, 900);
+        scores.record(20_001, 50);
+
+        let parsed = DailyScores::parse(&scores.contents());
+        assert_eq!(parsed.for_day(20_000), [900, 300]);
+        assert_eq!(parsed.for_day(20_001), [50]);
+        assert!(parsed.for_day(20_002).is_empty());
+    }
+
+    #[test]
+    fn leaderboard_keeps_the_best() {
+        let mut scores = DailyScores::default();
+        for score in 0..20 {
+            scores.record(1, score * 10);
+        }
+        assert_eq!(scores.for_day(1).len(), LEADERBOARD_SIZE);
+        assert_eq!(scores.for_day(1)[0], 190);
+        assert_eq!(scores.for_day(1)[LEADERBOARD_SIZE - 1], 100);
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let scores = DailyScores::parse("1 100\nyesterday 50\n1\n1 -5\n1 lots\n\n2 70 \n");
+        assert_eq!(scores.for_day(1), [100]);
+        assert_eq!(scores.for_day(2), [70]);
+    }
+}
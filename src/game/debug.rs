@@ -0,0 +1,337 @@
+//! Developer tools, only built with the `debug` feature: an F3 overlay with
+//! colliders and internals, and a cheat console opened with the backquote key.
+
+use crate::GameAssets;
+use crate::GameState;
+
+use super::Bullet;
+use super::Collider;
+use super::Enemy;
+use super::EnemyFireTimer;
+use super::EnemyKilled;
+use super::FrontEnemies;
+use super::Health;
+use super::Hp;
+use super::MyRng;
+use super::Player;
+use super::PlayerFireTimer;
+use super::Position;
+use super::collision;
+use super::collision::{CollisionLayers, Layer};
+
+use std::collections::BTreeMap;
+
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::ecs::entity::Entities;
+use bevy::input::ButtonState;
+use bevy::input::InputSystem;
+use bevy::input::common_conditions::input_just_pressed;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::math::bounding::{Aabb2d, BoundingVolume};
+use bevy::prelude::*;
+
+use rand::prelude::*;
+
+const HELP: &str = "god, hp <n>, spawn_wave, kill_all, set_seed <n>, timescale <x>";
+
+pub fn debug_plugin(app: &mut App) {
+    app.add_plugins(FrameTimeDiagnosticsPlugin::default())
+        .init_resource::<DebugState>()
+        .add_event::<ConsoleCommand>()
+        .add_systems(OnExit(GameState::AssetLoading), setup_debug)
+        .add_systems(PreUpdate, capture_keys.after(InputSystem))
+        .add_systems(
+            Update,
+            (
+                toggle_overlay.run_if(input_just_pressed(KeyCode::F3)),
+                toggle_console.run_if(
+                    input_just_pressed(KeyCode::Backquote).and(in_state(GameState::Running)),
+                ),
+                type_command,
+                run_commands.run_if(in_state(GameState::Running)),
+                apply_god_mode.run_if(in_state(GameState::Running)),
+                draw_colliders,
+                update_debug_text,
+            )
+                .chain(),
+        )
+        .add_systems(OnExit(GameState::Running), close_console);
+}
+
+#[derive(Resource, Default)]
+struct DebugState {
+    overlay: bool,
+    /// The line being typed, while the console is open
+    console: Option<String>,
+    god: bool,
+    /// Outcome of the last command
+    message: String,
+}
+
+#[derive(Event)]
+struct ConsoleCommand(String);
+
+#[derive(Component)]
+struct DebugText;
+
+fn setup_debug(mut commands: Commands, assets: Res<GameAssets>) {
+    commands.spawn((
+        Text::default(),
+        TextFont {
+            font: assets.font_press_start.clone(),
+            font_size: 10.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.5, 1., 0.5)),
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(20.0),
+            bottom: Val::Px(20.0),
+            ..default()
+        },
+        // Over everything else
+        GlobalZIndex(i32::MAX),
+        DebugText,
+    ));
+}
+
+fn toggle_overlay(mut state: ResMut<DebugState>) {
+    state.overlay = !state.overlay;
+}
+
+/// The game is paused while the console is open, so typing doesn't move the
+/// player.
+fn toggle_console(mut state: ResMut<DebugState>, mut time: ResMut<Time<Virtual>>) {
+    if state.console.take().is_some() {
+        time.unpause();
+    } else {
+        state.console = Some(String::new());
+        time.pause();
+    }
+}
+
+fn close_console(mut state: ResMut<DebugState>, mut time: ResMut<Time<Virtual>>) {
+    if state.console.take().is_some() {
+        time.unpause();
+    }
+}
+
+/// Keys typed into the console are for the console only, so Escape doesn't
+/// suspend the run and F5 and F9 don't touch the snapshot.
+fn capture_keys(state: Res<DebugState>, mut keyboard_input: ResMut<ButtonInput<KeyCode>>) {
+    if state.console.is_none() {
+        return;
+    }
+
+    let typed: Vec<_> = keyboard_input
+        .get_just_pressed()
+        .filter(|&&key| key != KeyCode::Backquote)
+        .copied()
+        .collect();
+    for key in typed {
+        keyboard_input.clear_just_pressed(key);
+    }
+}
+
+fn type_command(
+    mut state: ResMut<DebugState>,
+    mut keyboard_reader: EventReader<KeyboardInput>,
+    mut command_writer: EventWriter<ConsoleCommand>,
+) {
+    let Some(line) = state.console.as_mut() else {
+        keyboard_reader.clear();
+        return;
+    };
+
+    for input in keyboard_reader.read() {
+        if input.state != ButtonState::Pressed || input.key_code == KeyCode::Backquote {
+            continue;
+        }
+        match &input.logical_key {
+            Key::Enter => {
+                command_writer.write(ConsoleCommand(std::mem::take(line)));
+            }
+            Key::Backspace => {
+                line.pop();
+            }
+            Key::Space => line.push(' '),
+            Key::Character(text) => line.push_str(text),
+            _ => {}
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn run_commands(
+    mut commands: Commands,
+    mut command_reader: EventReader<ConsoleCommand>,
+    mut state: ResMut<DebugState>,
+    mut hp: ResMut<Hp>,
+    mut rng: ResMut<MyRng>,
+    mut time: ResMut<Time<Virtual>>,
+    enemy_query: Query<(Entity, &Transform, &Enemy, Option<&Position>), With<Health>>,
+    mut kill_writer: EventWriter<EnemyKilled>,
+) {
+    for ConsoleCommand(line) in command_reader.read() {
+        let mut words = line.split_whitespace();
+        let message = match (words.next(), words.next()) {
+            (Some("god"), None) => {
+                state.god = !state.god;
+                format!("god mode {}", if state.god { "on" } else { "off" })
+            }
+            (Some("hp"), Some(value)) => match value.parse() {
+                Ok(value) => {
+                    hp.0 = value;
+                    format!("hp set to {value}")
+                }
+                Err(_) => format!("bad hp: {value}"),
+            },
+            // The next wave comes in once the formation is gone
+            (Some("spawn_wave"), None) => {
+                for (entity, ..) in &enemy_query {
+                    commands.entity(entity).despawn();
+                }
+                "skipping to the next wave".to_string()
+            }
+            (Some("kill_all"), None) => {
+                for (entity, transform, &enemy, position) in &enemy_query {
+                    commands.entity(entity).despawn();
+                    kill_writer.write(EnemyKilled {
+                        position: position.copied(),
+                        translation: transform.translation,
+                        enemy,
+                    });
+                }
+                "killed every enemy".to_string()
+            }
+            (Some("set_seed"), Some(value)) => match value.parse() {
+                Ok(seed) => {
+                    rng.0 = StdRng::seed_from_u64(seed);
+                    format!("seed set to {seed}")
+                }
+                Err(_) => format!("bad seed: {value}"),
+            },
+            (Some("timescale"), Some(value)) => match value.parse::<f32>() {
+                Ok(scale) if scale > 0. => {
+                    time.set_relative_speed(scale);
+                    format!("timescale set to {scale}")
+                }
+                _ => format!("bad timescale: {value}"),
+            },
+            (None, _) => continue,
+            _ => format!("commands: {HELP}"),
+        };
+        state.message = message;
+    }
+}
+
+/// A god-mode player has no collider, so nothing can hit it.
+fn apply_god_mode(
+    mut commands: Commands,
+    state: Res<DebugState>,
+    query: Query<(Entity, &Transform, Has<Collider>), With<Player>>,
+) {
+    for (entity, transform, has_collider) in &query {
+        if state.god && has_collider {
+            commands
+                .entity(entity)
+                .remove::<(Collider, CollisionLayers)>();
+        } else if !state.god && !has_collider {
+            commands.entity(entity).insert((
+                Collider(Aabb2d::new(
+                    transform.translation.truncate(),
+                    transform.scale.truncate() / 2.,
+                )),
+                collision::PLAYER,
+            ));
+        }
+    }
+}
+
+fn draw_colliders(
+    mut gizmos: Gizmos,
+    state: Res<DebugState>,
+    query: Query<(&Collider, Option<&CollisionLayers>)>,
+) {
+    if !state.overlay {
+        return;
+    }
+
+    for (Collider(aabb), layers) in &query {
+        let color = match layers.map(|layers| layers.layer) {
+            Some(Layer::Player) => Color::srgb(0., 1., 0.),
+            Some(Layer::PlayerBullet) => Color::WHITE,
+            Some(Layer::EnemyBullet) => Color::srgb(1., 0.3, 0.3),
+            Some(Layer::Enemy) => Color::srgb(1., 0.6, 0.),
+            Some(Layer::Shield) => Color::srgb(0.3, 0.6, 1.),
+            Some(Layer::Pickup) => Color::srgb(1., 1., 0.),
+            None => Color::srgb(0.5, 0.5, 0.5),
+        };
+        gizmos.rect_2d(
+            Isometry2d::from_translation(aabb.center()),
+            aabb.half_size() * 2.,
+            color,
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn update_debug_text(
+    state: Res<DebugState>,
+    mut text_query: Query<&mut Text, With<DebugText>>,
+    diagnostics: Res<DiagnosticsStore>,
+    game_state: Res<State<GameState>>,
+    entities: &Entities,
+    counts: Query<(Has<Enemy>, Has<Bullet>), Or<(With<Enemy>, With<Bullet>)>>,
+    front_enemies: Option<Res<FrontEnemies>>,
+    player_fire: Option<Res<PlayerFireTimer>>,
+    enemy_fire: Option<Res<EnemyFireTimer>>,
+) {
+    let Ok(mut text) = text_query.single_mut() else {
+        return;
+    };
+
+    let mut lines = Vec::new();
+    if state.overlay {
+        let fps = diagnostics
+            .get(&FrameTimeDiagnosticsPlugin::FPS)
+            .and_then(|fps| fps.smoothed())
+            .unwrap_or_default();
+        lines.push(format!("FPS {fps:.0}  State {:?}", game_state.get()));
+
+        let enemies = counts.iter().filter(|&(enemy, _)| enemy).count();
+        let bullets = counts.iter().filter(|&(_, bullet)| bullet).count();
+        lines.push(format!(
+            "Entities {}  Enemies {enemies}  Bullets {bullets}",
+            entities.len()
+        ));
+
+        if let Some(front_enemies) = front_enemies {
+            let front: BTreeMap<_, _> = front_enemies.0.iter().collect();
+            let front: Vec<_> = front
+                .iter()
+                .map(|(col, row)| format!("{col}:{row}"))
+                .collect();
+            lines.push(format!("Front {}", front.join(" ")));
+        }
+        if let (Some(player_fire), Some(enemy_fire)) = (player_fire, enemy_fire) {
+            lines.push(format!(
+                "Fire timers  player {:.0}%  enemy {:.0}%",
+                player_fire.0.fraction() * 100.,
+                enemy_fire.0.fraction() * 100.
+            ));
+        }
+    }
+
+    if let Some(line) = &state.console {
+        if !state.message.is_empty() {
+            lines.push(state.message.clone());
+        }
+        lines.push(format!("> {line}_"));
+    }
+
+    let contents = lines.join("\n");
+    if text.0 != contents {
+        text.0 = contents;
+    }
+}
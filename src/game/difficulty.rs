@@ -0,0 +1,89 @@
+use crate::Difficulty;
+use crate::GameMode;
+
+use super::targeting::TargetingPolicy;
+
+use bevy::prelude::*;
+
+/// How the chosen difficulty and game mode scale the game.
+#[derive(Resource, Clone, Copy)]
+pub struct Preset {
+    pub starting_hp: u8,
+    pub enemy_speed: f32,
+    pub enemy_bullet_speed: f32,
+    pub score_multiplier: f32,
+    /// Whether crossing a score threshold gives back a life
+    pub extra_lives: bool,
+    pub targeting: TargetingPolicy,
+}
+
+impl Preset {
+    pub fn new(difficulty: Difficulty, mode: GameMode) -> Self {
+        let preset = match difficulty {
+            Difficulty::Easy => Self {
+                starting_hp: 7,
+                enemy_speed: 0.8,
+                enemy_bullet_speed: 0.8,
+                score_multiplier: 0.5,
+                extra_lives: true,
+                targeting: TargetingPolicy {
+                    player_bias: 1.,
+                    fire_rate: 3.,
+                    aggression: 0.25,
+                    bullet_cap: 20,
+                    ..default()
+                },
+            },
+            Difficulty::Normal => Self {
+                starting_hp: 5,
+                enemy_speed: 1.,
+                enemy_bullet_speed: 1.,
+                score_multiplier: 1.,
+                extra_lives: true,
+                targeting: TargetingPolicy::default(),
+            },
+            Difficulty::Hard => Self {
+                starting_hp: 4,
+                enemy_speed: 1.2,
+                enemy_bullet_speed: 1.2,
+                score_multiplier: 1.5,
+                extra_lives: true,
+                targeting: TargetingPolicy {
+                    player_bias: 5.,
+                    shooters: 2,
+                    aggression: 0.75,
+                    bullet_cap: 60,
+                    ..default()
+                },
+            },
+            Difficulty::Insane => Self {
+                starting_hp: 3,
+                enemy_speed: 1.5,
+                enemy_bullet_speed: 1.4,
+                score_multiplier: 2.5,
+                extra_lives: true,
+                targeting: TargetingPolicy {
+                    player_bias: 8.,
+                    shooters: 3,
+                    fire_rate: 5.,
+                    aggression: 1.,
+                    bullet_cap: 100,
+                },
+            },
+        };
+
+        match mode {
+            GameMode::OneLife => Self {
+                starting_hp: 1,
+                score_multiplier: preset.score_multiplier * 2.,
+                extra_lives: false,
+                ..preset
+            },
+            GameMode::Standard | GameMode::TimeAttack | GameMode::Survival => preset,
+        }
+    }
+
+    pub fn points(&self, points: u32) -> u32 {
+        (points as f32 * self.score_multiplier).round() as u32
+    }
+}
@@ -0,0 +1,191 @@
+use crate::GameAssets;
+use crate::GameState;
+
+use super::OnGameScreen;
+use super::PlayerHit;
+use super::RunStats;
+use super::Wave;
+use super::stats::collect_stats;
+use super::versus::lockstep_ready;
+
+use bevy::prelude::*;
+
+/// How often the director looks at recent performance.
+const EVALUATION_INTERVAL: f32 = 10.;
+const STEP: f32 = 0.05;
+pub const MIN_SCALE: f32 = 0.75;
+pub const MAX_SCALE: f32 = 1.5;
+/// Players hitting at least this share of their shots get a harder game.
+const TARGET_ACCURACY: f32 = 0.4;
+/// Waves cleared faster than this make the game harder, and waves taking
+/// more than twice as long make it easier.
+const TARGET_WAVE_TIME: f32 = 45.;
+
+pub fn director_plugin(app: &mut App) {
+    app.register_type::<Director>()
+        .register_type::<DirectorOverlay>()
+        .add_systems(OnEnter(GameState::Running), setup_director)
+        .add_systems(
+            FixedUpdate,
+            (observe_player, evaluate_player)
+                .chain()
+                .after(collect_stats)
+                .run_if(in_state(GameState::Running).and(lockstep_ready)),
+        )
+        .add_systems(
+            Update,
+            (toggle_overlay, update_overlay).run_if(in_state(GameState::Running)),
+        );
+}
+
+/// Adapts the game to how well the player is doing, on top of the chosen
+/// difficulty.
+#[derive(Resource, Reflect, Clone)]
+#[reflect(Resource)]
+pub struct Director {
+    /// Shots counted by [`RunStats`] when the window began
+    pub shots_before: u32,
+    /// Hits counted by [`RunStats`] when the window began
+    pub hits_before: u32,
+    pub hits_taken: u32,
+    pub window: Timer,
+    pub wave: u32,
+    pub wave_time: f32,
+    /// Multiplies the enemy fire rate
+    pub fire_scale: f32,
+    /// Multiplies the enemy march speed
+    pub speed_scale: f32,
+}
+
+impl Default for Director {
+    fn default() -> Self {
+        Self {
+            shots_before: 0,
+            hits_before: 0,
+            hits_taken: 0,
+            window: Timer::from_seconds(EVALUATION_INTERVAL, TimerMode::Repeating),
+            wave: 0,
+            wave_time: 0.,
+            fire_scale: 1.,
+            speed_scale: 1.,
+        }
+    }
+}
+
+impl Director {
+    fn shots(&self, run: &RunStats) -> u32 {
+        run.shots.saturating_sub(self.shots_before)
+    }
+
+    /// Shots that hit an enemy or one of their bullets, like the run's own
+    /// accuracy.
+    fn accuracy(&self, run: &RunStats) -> f32 {
+        let hits = run.hits.saturating_sub(self.hits_before);
+        match self.shots(run) {
+            0 => 0.,
+            shots => (hits as f32 / shots as f32).min(1.),
+        }
+    }
+
+    fn nudge(&mut self, amount: f32) {
+        self.fire_scale = (self.fire_scale + amount).clamp(MIN_SCALE, MAX_SCALE);
+        self.speed_scale = (self.speed_scale + amount / 2.).clamp(MIN_SCALE, MAX_SCALE);
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct DirectorOverlay;
+
+pub fn setup_director(mut commands: Commands, assets: Res<GameAssets>) {
+    commands.insert_resource(Director::default());
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(20.0),
+            bottom: Val::Px(20.0),
+            ..default()
+        },
+        Text::default(),
+        TextFont {
+            font_size: 12.0,
+            font: assets.font_press_start.clone(),
+            ..default()
+        },
+        Visibility::Hidden,
+        DirectorOverlay,
+        OnGameScreen,
+    ));
+}
+
+fn observe_player(mut director: ResMut<Director>, mut hit_reader: EventReader<PlayerHit>) {
+    director.hits_taken += hit_reader.read().count() as u32;
+}
+
+fn evaluate_player(
+    mut director: ResMut<Director>,
+    run: Res<RunStats>,
+    wave: Res<Wave>,
+    time: Res<Time<Fixed>>,
+) {
+    director.wave_time += time.delta_secs();
+
+    // Time per wave
+    if wave.0 != director.wave {
+        if director.wave != 0 {
+            if director.wave_time < TARGET_WAVE_TIME {
+                director.nudge(STEP);
+            } else if director.wave_time > TARGET_WAVE_TIME * 2. {
+                director.nudge(-STEP);
+            }
+        }
+        director.wave = wave.0;
+        director.wave_time = 0.;
+    }
+
+    if !director.window.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    // Getting hit eases off, and accurate shooting without getting hit
+    // turns things up
+    if director.hits_taken > 0 {
+        let amount = -STEP * director.hits_taken as f32;
+        director.nudge(amount);
+    } else if director.shots(&run) > 0 && director.accuracy(&run) >= TARGET_ACCURACY {
+        director.nudge(STEP);
+    }
+
+    director.shots_before = run.shots;
+    director.hits_before = run.hits;
+    director.hits_taken = 0;
+}
+
+fn toggle_overlay(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut query: Query<&mut Visibility, With<DirectorOverlay>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F2) {
+        for mut visibility in &mut query {
+            visibility.toggle_visible_hidden();
+        }
+    }
+}
+
+fn update_overlay(
+    director: Res<Director>,
+    run: Res<RunStats>,
+    mut query: Query<&mut Text, With<DirectorOverlay>>,
+) {
+    if let Ok(mut text) = query.single_mut() {
+        **text = format!(
+            "Fire x{:.2}\nSpeed x{:.2}\nAccuracy {:.0}%\nHits {}\nWave {:.1}s",
+            director.fire_scale,
+            director.speed_scale,
+            director.accuracy(&run) * 100.,
+            director.hits_taken,
+            director.wave_time,
+        );
+    }
+}
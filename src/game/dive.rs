@@ -0,0 +1,210 @@
+use crate::GameState;
+
+use super::BOTTOM_WALL;
+use super::ENEMY_BULLET_SPEED;
+use super::Enemy;
+use super::FrontEnemies;
+use super::MyRng;
+use super::Player;
+use super::Position;
+use super::TOP_WALL;
+use super::enemy_movement;
+use super::spawn_enemy_bullet;
+use super::versus::lockstep_ready;
+
+use bevy::prelude::*;
+
+use rand::prelude::*;
+
+const DIVE_INTERVAL: f32 = 3.0;
+const MAX_DIVERS: usize = 3;
+const DIVE_FIRE_INTERVAL: f32 = 0.6;
+
+const ATTACK_TIME: f32 = 2.0;
+const RETURN_TIME: f32 = 1.5;
+const EXIT_SPEED: f32 = 300.;
+/// How far past the top and bottom walls divers travel when wrapping around.
+const WRAP_MARGIN: f32 = 40.;
+
+pub fn dive_plugin(app: &mut App) {
+    app.register_type::<Dive>()
+        .register_type::<DiveTimer>()
+        .add_systems(OnEnter(GameState::Running), setup_dives)
+        .add_systems(
+            FixedUpdate,
+            (start_dives, dive_movement.after(enemy_movement), dive_fire)
+                .run_if(in_state(GameState::Running).and(lockstep_ready)),
+        );
+}
+
+#[derive(Resource, Reflect, Clone)]
+#[reflect(Resource)]
+pub struct DiveTimer(pub Timer);
+
+impl Default for DiveTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(DIVE_INTERVAL, TimerMode::Repeating))
+    }
+}
+
+/// An enemy that has broken formation. `slot` follows the formation's march
+/// so the enemy knows where to return to.
+#[derive(Component, Reflect, Clone)]
+#[reflect(Component)]
+pub struct Dive {
+    pub slot: Vec3,
+    pub phase: DivePhase,
+    pub progress: f32,
+    pub fire_timer: Timer,
+}
+
+#[derive(Reflect, Clone, Copy)]
+pub enum DivePhase {
+    /// Curving down towards where the player was when the dive started
+    Attack { path: [Vec2; 4] },
+    /// Flying off the bottom of the screen before wrapping around to the top
+    Exit,
+    /// Heading back to the formation slot
+    Return { from: Vec2 },
+}
+
+impl Dive {
+    pub fn new(slot: Vec3, phase: DivePhase) -> Self {
+        Self {
+            slot,
+            phase,
+            progress: 0.,
+            fire_timer: Timer::from_seconds(DIVE_FIRE_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+/// Where an enemy sits in the formation, whether or not it's diving.
+pub fn slot_translation(transform: &Transform, dive: Option<&Dive>) -> Vec3 {
+    dive.map_or(transform.translation, |dive| dive.slot)
+}
+
+pub fn setup_dives(mut commands: Commands) {
+    commands.insert_resource(DiveTimer::default());
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn start_dives(
+    mut commands: Commands,
+    mut dive_timer: ResMut<DiveTimer>,
+    time: Res<Time<Fixed>>,
+    enemy_query: Query<(Entity, &Transform, &Position), (With<Enemy>, Without<Dive>)>,
+    dive_query: Query<(), With<Dive>>,
+    player_query: Query<&Transform, With<Player>>,
+    front_enemies: Res<FrontEnemies>,
+    mut rng: ResMut<MyRng>,
+) {
+    if !dive_timer.0.tick(time.delta()).finished() || dive_query.iter().count() >= MAX_DIVERS {
+        return;
+    }
+
+    let Some((entity, transform)) = enemy_query
+        .iter()
+        .filter_map(|(entity, transform, Position { row, col })| {
+            front_enemies
+                .0
+                .get(col)
+                .is_some_and(|front_row| row == front_row)
+                .then_some((entity, transform))
+        })
+        .choose(&mut rng.0)
+    else {
+        return;
+    };
+
+    let target_x = player_query
+        .single()
+        .map_or(0., |transform| transform.translation.x);
+    let start = transform.translation.truncate();
+    // Loop up and away from the player first, then swoop down at them
+    let side = if start.x < target_x { -1. } else { 1. };
+    let path = [
+        start,
+        start + Vec2::new(side * 150., 100.),
+        Vec2::new(target_x + side * 200., start.y - 150.),
+        Vec2::new(target_x, BOTTOM_WALL + 60.),
+    ];
+
+    commands
+        .entity(entity)
+        .insert(Dive::new(transform.translation, DivePhase::Attack { path }));
+}
+
+fn dive_movement(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Transform, &mut Dive)>,
+    time: Res<Time<Fixed>>,
+    mut rng: ResMut<MyRng>,
+) {
+    for (entity, mut transform, mut dive) in &mut query {
+        match dive.phase {
+            DivePhase::Attack { path } => {
+                dive.progress = (dive.progress + time.delta_secs() / ATTACK_TIME).min(1.);
+                transform.translation = bezier(path, dive.progress).extend(0.);
+
+                if dive.progress >= 1. {
+                    dive.progress = 0.;
+                    dive.phase = if rng.0.random_bool(0.5) {
+                        DivePhase::Exit
+                    } else {
+                        DivePhase::Return { from: path[3] }
+                    };
+                }
+            }
+            DivePhase::Exit => {
+                transform.translation.y -= EXIT_SPEED * time.delta_secs();
+
+                if transform.translation.y < BOTTOM_WALL - WRAP_MARGIN {
+                    let from = Vec2::new(dive.slot.x, TOP_WALL + WRAP_MARGIN);
+                    transform.translation = from.extend(0.);
+                    dive.phase = DivePhase::Return { from };
+                }
+            }
+            DivePhase::Return { from } => {
+                dive.progress = (dive.progress + time.delta_secs() / RETURN_TIME).min(1.);
+                // The slot keeps marching, so the path is rebuilt every tick
+                let slot = dive.slot.truncate();
+                let mid_y = (from.y + slot.y) / 2.;
+                let path = [
+                    from,
+                    Vec2::new(from.x, mid_y),
+                    Vec2::new(slot.x, mid_y),
+                    slot,
+                ];
+                transform.translation = bezier(path, dive.progress).extend(0.);
+
+                if dive.progress >= 1. {
+                    commands.entity(entity).remove::<Dive>();
+                }
+            }
+        }
+    }
+}
+
+fn dive_fire(
+    mut commands: Commands,
+    mut query: Query<(&Transform, &mut Dive)>,
+    time: Res<Time<Fixed>>,
+) {
+    for (transform, mut dive) in &mut query {
+        if matches!(dive.phase, DivePhase::Attack { .. })
+            && dive.fire_timer.tick(time.delta()).just_finished()
+        {
+            spawn_enemy_bullet(
+                &mut commands,
+                transform.translation - Vec3::new(0., 15., 0.),
+                Vec2::NEG_Y * ENEMY_BULLET_SPEED,
+            );
+        }
+    }
+}
+
+fn bezier([p0, p1, p2, p3]: [Vec2; 4], t: f32) -> Vec2 {
+    let u = 1. - t;
+    p0 * u * u * u + p1 * 3. * u * u * t + p2 * 3. * u * t * t + p3 * t * t * t
+}
@@ -0,0 +1,437 @@
+//! The level editor: paint enemies onto the formation grid, drag the shields
+//! around and tune the wave parameters, then playtest or save the level.
+
+use crate::GameAssets;
+use crate::GameState;
+use crate::Players;
+use crate::despawn_screen;
+
+use super::BOTTOM_WALL;
+use super::CampaignStage;
+use super::DailyChallenge;
+use super::Enemy;
+use super::LEFT_WALL;
+use super::Levels;
+use super::MarchStyle;
+use super::RIGHT_WALL;
+use super::TOP_WALL;
+use super::board::{ENEMY_COLS, ENEMY_ROWS, SHIELD_SCALE, enemy_color};
+use super::level::{MAX_PARAMETER, MIN_PARAMETER};
+
+use bevy::input::common_conditions::input_just_pressed;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+/// The grid is drawn smaller than the formation, so all of it fits on screen.
+const CELL_SPACING: f32 = 20.;
+const CELL_SIZE: f32 = 16.;
+/// Where the front row of the grid is drawn.
+const GRID_BOTTOM: f32 = 20.;
+const EMPTY_CELL: Color = Color::srgb(0.15, 0.15, 0.15);
+
+/// Enemy types that can be painted, picked with the number keys.
+const BRUSHES: [Enemy; 5] = [
+    Enemy::Normal,
+    Enemy::Sniper,
+    Enemy::Weaver,
+    Enemy::Seeker,
+    Enemy::Lancer,
+];
+const BRUSH_KEYS: [KeyCode; 5] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+];
+
+const PARAMETER_STEP: f32 = 0.1;
+
+pub fn editor_plugin(app: &mut App) {
+    app.init_resource::<Editor>()
+        .add_systems(OnEnter(GameState::Editor), setup_editor)
+        .add_systems(
+            Update,
+            (
+                drag_shields,
+                paint_cells,
+                edit_parameters,
+                editor_shortcuts,
+                update_cells,
+                update_editor_text,
+            )
+                .chain()
+                .run_if(in_state(GameState::Editor)),
+        )
+        .add_systems(OnExit(GameState::Editor), despawn_screen::<OnEditorScreen>)
+        .add_systems(
+            Last,
+            (
+                leave_playtest.run_if(input_just_pressed(KeyCode::Escape)),
+                skip_game_over,
+            )
+                .run_if(in_state(GameState::Running).and(resource_exists::<Playtest>)),
+        );
+}
+
+/// The level being played comes straight from the editor, which the run goes
+/// back to when it ends. Nothing about it is saved or recorded.
+#[derive(Resource)]
+pub struct Playtest;
+
+#[derive(Component)]
+struct OnEditorScreen;
+
+#[derive(Component)]
+struct Cell {
+    row: usize,
+    col: usize,
+}
+
+/// Index into the level's shields.
+#[derive(Component)]
+struct EditorShield(usize);
+
+#[derive(Component)]
+struct EditorText;
+
+#[derive(Resource)]
+struct Editor {
+    brush: Enemy,
+    /// The shield being dragged with the mouse
+    dragging: Option<usize>,
+    /// Outcome of the last save or playtest
+    message: String,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self {
+            brush: Enemy::Normal,
+            dragging: None,
+            message: String::new(),
+        }
+    }
+}
+
+fn setup_editor(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    levels: Res<Levels>,
+    mut editor: ResMut<Editor>,
+) {
+    let level = &levels.endless;
+    commands.remove_resource::<Playtest>();
+    editor.dragging = None;
+    editor.message.clear();
+
+    for row in 0..ENEMY_ROWS {
+        for col in 0..ENEMY_COLS {
+            commands.spawn((
+                Transform {
+                    translation: cell_translation(row, col).extend(0.),
+                    scale: Vec3::splat(CELL_SIZE),
+                    ..default()
+                },
+                Sprite {
+                    color: level.grid[row][col].map_or(EMPTY_CELL, enemy_color),
+                    ..default()
+                },
+                Cell { row, col },
+                OnEditorScreen,
+            ));
+        }
+    }
+
+    for (index, shield) in level.shields.iter().enumerate() {
+        commands.spawn((
+            Transform {
+                translation: shield.extend(0.),
+                scale: SHIELD_SCALE,
+                ..default()
+            },
+            Sprite {
+                color: Color::srgb(0., 1., 0.5),
+                ..default()
+            },
+            EditorShield(index),
+            OnEditorScreen,
+        ));
+    }
+
+    commands.spawn((
+        Text::default(),
+        TextFont {
+            font: assets.font_press_start.clone(),
+            font_size: 12.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(20.0),
+            top: Val::Px(20.0),
+            ..default()
+        },
+        EditorText,
+        OnEditorScreen,
+    ));
+}
+
+fn cell_translation(row: usize, col: usize) -> Vec2 {
+    Vec2::new(
+        (col as f32 - (ENEMY_COLS as f32 - 1.) / 2.) * CELL_SPACING,
+        GRID_BOTTOM + row as f32 * CELL_SPACING,
+    )
+}
+
+/// Where the mouse is, in world coordinates.
+fn cursor_position(
+    window_query: &Query<&Window, With<PrimaryWindow>>,
+    camera_query: &Query<(&Camera, &GlobalTransform)>,
+) -> Option<Vec2> {
+    let cursor = window_query.single().ok()?.cursor_position()?;
+    let (camera, transform) = camera_query.single().ok()?;
+    camera.viewport_to_world_2d(transform, cursor).ok()
+}
+
+fn drag_shields(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut editor: ResMut<Editor>,
+    mut levels: ResMut<Levels>,
+    mut shield_query: Query<(&EditorShield, &mut Transform)>,
+) {
+    if mouse_input.just_released(MouseButton::Left) {
+        editor.dragging = None;
+    }
+    let Some(cursor) = cursor_position(&window_query, &camera_query) else {
+        return;
+    };
+
+    if mouse_input.just_pressed(MouseButton::Left) {
+        editor.dragging = levels.endless.shields.iter().position(|&shield| {
+            (cursor - shield)
+                .abs()
+                .cmple(SHIELD_SCALE.truncate() / 2.)
+                .all()
+        });
+    }
+
+    let Some(index) = editor.dragging else {
+        return;
+    };
+    let position = cursor.clamp(
+        Vec2::new(LEFT_WALL, BOTTOM_WALL),
+        Vec2::new(RIGHT_WALL, TOP_WALL),
+    );
+    levels.endless.shields[index] = position;
+    for (shield, mut transform) in &mut shield_query {
+        if shield.0 == index {
+            transform.translation = position.extend(0.);
+        }
+    }
+}
+
+/// Left click paints the brush, right click erases. Holding either down
+/// paints every cell the mouse passes over.
+fn paint_cells(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    editor: Res<Editor>,
+    mut levels: ResMut<Levels>,
+) {
+    if editor.dragging.is_some() {
+        return;
+    }
+    let enemy = if mouse_input.pressed(MouseButton::Left) {
+        Some(editor.brush)
+    } else if mouse_input.pressed(MouseButton::Right) {
+        None
+    } else {
+        return;
+    };
+    let Some(cursor) = cursor_position(&window_query, &camera_query) else {
+        return;
+    };
+
+    let col = (cursor.x / CELL_SPACING + (ENEMY_COLS as f32 - 1.) / 2.).round();
+    let row = ((cursor.y - GRID_BOTTOM) / CELL_SPACING).round();
+    if !(0. ..ENEMY_COLS as f32).contains(&col) || !(0. ..ENEMY_ROWS as f32).contains(&row) {
+        return;
+    }
+    let (row, col) = (row as usize, col as usize);
+
+    // Only inside the cell, not in the gaps between them
+    if (cursor - cell_translation(row, col)).abs().max_element() > CELL_SIZE / 2. {
+        return;
+    }
+    if levels.endless.grid[row][col] != enemy {
+        levels.endless.grid[row][col] = enemy;
+    }
+}
+
+fn edit_parameters(keyboard_input: Res<ButtonInput<KeyCode>>, mut levels: ResMut<Levels>) {
+    if keyboard_input.just_pressed(KeyCode::KeyM) {
+        let level = &mut levels.endless;
+        level.march = match level.march {
+            None => Some(MarchStyle::Smooth),
+            Some(MarchStyle::Smooth) => Some(MarchStyle::Stepped),
+            Some(MarchStyle::Stepped) => None,
+        };
+    }
+
+    let step = |pressed: bool, value: f32, sign: f32| {
+        if pressed {
+            // Rounded, so the level file doesn't fill up with float noise
+            ((value + sign * PARAMETER_STEP) * 10.)
+                .round()
+                .clamp(MIN_PARAMETER * 10., MAX_PARAMETER * 10.)
+                / 10.
+        } else {
+            value
+        }
+    };
+    for (key, sign) in [(KeyCode::ArrowUp, 1.), (KeyCode::ArrowDown, -1.)] {
+        let speed = step(keyboard_input.just_pressed(key), levels.endless.speed, sign);
+        if speed != levels.endless.speed {
+            levels.endless.speed = speed;
+        }
+    }
+    for (key, sign) in [(KeyCode::ArrowRight, 1.), (KeyCode::ArrowLeft, -1.)] {
+        let fire_rate = step(
+            keyboard_input.just_pressed(key),
+            levels.endless.fire_rate,
+            sign,
+        );
+        if fire_rate != levels.endless.fire_rate {
+            levels.endless.fire_rate = fire_rate;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn editor_shortcuts(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut editor: ResMut<Editor>,
+    levels: Res<Levels>,
+    mut players: ResMut<Players>,
+    mut stage: ResMut<CampaignStage>,
+    mut daily: ResMut<DailyChallenge>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    let level = &levels.endless;
+    for (key, brush) in BRUSH_KEYS.into_iter().zip(BRUSHES) {
+        if keyboard_input.just_pressed(key) {
+            editor.brush = brush;
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Enter) {
+        // Waves without enemies would be cleared as soon as they start
+        if level.enemy_count() == 0 {
+            editor.message = "Place some enemies first".to_string();
+        } else {
+            *players = Players::One;
+            *stage = CampaignStage(None);
+            *daily = DailyChallenge(None);
+            commands.insert_resource(Playtest);
+            game_state.set(GameState::Running);
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::KeyS) {
+        editor.message = if level.enemy_count() == 0 {
+            "Place some enemies first".to_string()
+        } else {
+            match level.save() {
+                Ok(()) => "Level saved".to_string(),
+                Err(err) => format!("Failed to save level: {err}"),
+            }
+        };
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        game_state.set(GameState::Menu);
+    }
+}
+
+fn update_cells(levels: Res<Levels>, mut query: Query<(&Cell, &mut Sprite)>) {
+    if !levels.is_changed() {
+        return;
+    }
+
+    for (cell, mut sprite) in &mut query {
+        sprite.color = levels.endless.grid[cell.row][cell.col].map_or(EMPTY_CELL, enemy_color);
+    }
+}
+
+fn update_editor_text(
+    levels: Res<Levels>,
+    editor: Res<Editor>,
+    mut query: Query<&mut Text, With<EditorText>>,
+) {
+    if !levels.is_changed() && !editor.is_changed() {
+        return;
+    }
+
+    let level = &levels.endless;
+    for mut text in &mut query {
+        **text = format!(
+            "LEVEL EDITOR\n\n\
+             Brush: {} (1-5)\n\
+             Left click paints, right click erases\n\
+             Drag shields to move them\n\n\
+             March: {} (M)\n\
+             Speed: {:.1} (Up/Down)\n\
+             Fire rate: {:.1} (Left/Right)\n\n\
+             Enter to playtest, S to save, Esc to go back\n\n\
+             {}",
+            editor.brush.name(),
+            level.march_name(),
+            level.speed,
+            level.fire_rate,
+            editor.message
+        );
+    }
+}
+
+fn leave_playtest(mut game_state: ResMut<NextState<GameState>>) {
+    game_state.set(GameState::Editor);
+}
+
+/// Whatever ended the playtest, it's back to the editor instead of the
+/// game-over screen, where the run would be recorded.
+fn skip_game_over(mut game_state: ResMut<NextState<GameState>>) {
+    if matches!(*game_state, NextState::Pending(GameState::GameOver)) {
+        game_state.set(GameState::Editor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn playtests_skip_the_game_over_screen() {
+        let mut world = World::new();
+        world.insert_resource(NextState::Pending(GameState::GameOver));
+        world.run_system_once(skip_game_over).unwrap();
+        assert!(matches!(
+            *world.resource::<NextState<GameState>>(),
+            NextState::Pending(GameState::Editor)
+        ));
+
+        // Anything else goes ahead
+        world.insert_resource(NextState::Pending(GameState::Menu));
+        world.run_system_once(skip_game_over).unwrap();
+        assert!(matches!(
+            *world.resource::<NextState<GameState>>(),
+            NextState::Pending(GameState::Menu)
+        ));
+    }
+}
@@ -0,0 +1,86 @@
+use crate::GameAssets;
+use crate::GameState;
+
+use super::OnGameScreen;
+
+use bevy::prelude::*;
+
+const POPUP_TIME: f32 = 0.8;
+const POPUP_RISE_SPEED: f32 = 40.;
+const POPUP_FONT_SIZE: f32 = 12.;
+
+const HIT_FLASH_TIME: f32 = 0.08;
+
+pub fn feedback_plugin(app: &mut App) {
+    app.register_type::<Popup>()
+        .register_type::<HitFlash>()
+        .add_systems(
+            Update,
+            (update_popups, update_hit_flashes).run_if(in_state(GameState::Running)),
+        );
+}
+
+/// Floating text that rises and fades out.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct Popup(Timer);
+
+/// A white overlay on an enemy that just took damage.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct HitFlash(Timer);
+
+pub fn spawn_popup(commands: &mut Commands, assets: &GameAssets, translation: Vec3, text: String) {
+    commands.spawn((
+        Text2d::new(text),
+        TextFont {
+            font: assets.font_press_start.clone(),
+            font_size: POPUP_FONT_SIZE,
+            ..default()
+        },
+        TextColor(Color::srgb(1., 0.9, 0.4)),
+        Transform::from_translation(translation.with_z(10.)),
+        Popup(Timer::from_seconds(POPUP_TIME, TimerMode::Once)),
+        OnGameScreen,
+    ));
+}
+
+pub fn flash(commands: &mut Commands, entity: Entity) {
+    commands.spawn((
+        Sprite {
+            color: Color::WHITE,
+            ..default()
+        },
+        Transform::from_xyz(0., 0., 0.1),
+        HitFlash(Timer::from_seconds(HIT_FLASH_TIME, TimerMode::Once)),
+        ChildOf(entity),
+    ));
+}
+
+fn update_popups(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Transform, &mut TextColor, &mut Popup)>,
+    time: Res<Time>,
+) {
+    for (entity, mut transform, mut color, mut popup) in &mut query {
+        if popup.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        transform.translation.y += POPUP_RISE_SPEED * time.delta_secs();
+        color.0.set_alpha(popup.0.fraction_remaining());
+    }
+}
+
+fn update_hit_flashes(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut HitFlash)>,
+    time: Res<Time>,
+) {
+    for (entity, mut hit_flash) in &mut query {
+        if hit_flash.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
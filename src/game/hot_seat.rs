@@ -0,0 +1,78 @@
+use crate::GameState;
+
+use super::Board;
+use super::Bullet;
+use super::Enemy;
+use super::Laser;
+use super::Level;
+use super::PlayerHit;
+use super::Shield;
+use super::WeakPoint;
+use super::board::CurrentBoard;
+use super::lives::destroy_player;
+
+use bevy::prelude::*;
+
+pub fn hot_seat_plugin(app: &mut App) {
+    app.add_systems(
+        FixedPostUpdate,
+        switch_player
+            .after(destroy_player)
+            .run_if(in_state(GameState::Running).and(resource_exists::<HotSeat>)),
+    )
+    .add_systems(OnExit(GameState::Running), remove_hot_seat);
+}
+
+/// Alternating two-player state. The board of the player waiting for their
+/// turn is stashed here until play switches back to them.
+#[derive(Resource, Clone)]
+pub struct HotSeat {
+    pub current: u8,
+    waiting: Board,
+}
+
+impl HotSeat {
+    pub fn new(hp: u8, level: &Level) -> Self {
+        Self {
+            current: 1,
+            waiting: Board::fresh(hp, level),
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn switch_player(
+    mut commands: Commands,
+    mut event_reader: EventReader<PlayerHit>,
+    mut hot_seat: ResMut<HotSeat>,
+    current_board: CurrentBoard,
+    board_query: Query<
+        Entity,
+        Or<(
+            With<Enemy>,
+            With<Shield>,
+            With<Bullet>,
+            With<WeakPoint>,
+            With<Laser>,
+        )>,
+    >,
+    level: Res<Level>,
+) {
+    // Losing a life hands over to the other player, unless they are out
+    if event_reader.read().count() == 0 || hot_seat.waiting.hp == 0 {
+        return;
+    }
+
+    let board = current_board.capture();
+
+    for entity in &board_query {
+        commands.entity(entity).despawn();
+    }
+
+    std::mem::replace(&mut hot_seat.waiting, board).spawn(&mut commands, &level);
+    hot_seat.current = 3 - hot_seat.current;
+}
+
+fn remove_hot_seat(mut commands: Commands) {
+    commands.remove_resource::<HotSeat>();
+}
@@ -0,0 +1,369 @@
+//! Formation layouts, shields and wave parameters, kept in `.level` files of
+//! `key value` lines under `assets/levels`. The endless level is the one the
+//! editor works on; every campaign stage has a level of its own.
+
+use crate::GameAssets;
+use crate::GameState;
+
+use super::BOSS_WAVE_INTERVAL;
+use super::Enemy;
+use super::MarchStyle;
+use super::board::{ENEMY_COLS, ENEMY_ROWS};
+use super::boss::BossStyle;
+
+use std::fs;
+use std::io;
+
+use bevy::asset::io::Reader;
+use bevy::asset::io::file::FileAssetReader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+
+/// Where the endless level is loaded from and saved to, inside the assets
+/// folder.
+const LEVEL_PATH: &str = "levels/default.level";
+
+/// Bounds of a level's speed and fire rate.
+pub const MIN_PARAMETER: f32 = 0.1;
+pub const MAX_PARAMETER: f32 = 5.;
+/// Bosses need some health to be beaten, and not so much that working out
+/// their phase overflows.
+pub const MAX_BOSS_HP: u32 = 10_000;
+
+/// One letter per enemy type in the `row` lines of a level file.
+const LETTERS: [(char, Enemy); 5] = [
+    ('N', Enemy::Normal),
+    ('S', Enemy::Sniper),
+    ('W', Enemy::Weaver),
+    ('H', Enemy::Seeker),
+    ('L', Enemy::Lancer),
+];
+
+pub fn level_plugin(app: &mut App) {
+    app.init_asset::<Level>()
+        .init_asset_loader::<LevelLoader>()
+        .add_systems(OnExit(GameState::AssetLoading), load_levels);
+}
+
+/// The formation every wave starts with, along with the shields, how fast
+/// the formation marches and fires, and the boss at the end.
+///
+/// Loaded as an asset and copied into `Levels`. The level being played is
+/// also a resource.
+#[derive(Asset, Resource, TypePath, Clone)]
+pub struct Level {
+    pub name: String,
+    /// Indexed by `[row][col]`, with row 0 at the front of the formation
+    pub(super) grid: [[Option<Enemy>; ENEMY_COLS]; ENEMY_ROWS],
+    pub shields: Vec<Vec2>,
+    /// `None` alternates between smooth and stepped marching every wave
+    pub(super) march: Option<MarchStyle>,
+    pub speed: f32,
+    pub fire_rate: f32,
+    /// Formation waves before the boss. Endless runs ignore it and keep
+    /// going, with a boss every few waves.
+    pub waves: u32,
+    pub boss_hp: u32,
+    pub boss_style: BossStyle,
+    pub background: Color,
+}
+
+/// Every level there is, copied out of the loaded assets.
+#[derive(Resource)]
+pub struct Levels {
+    pub endless: Level,
+    /// Campaign stages, in order
+    pub stages: Vec<Level>,
+}
+
+impl Default for Level {
+    /// The classic formation: tougher enemies with fancier shots sit further
+    /// back.
+    fn default() -> Self {
+        let mut grid = [[None; ENEMY_COLS]; ENEMY_ROWS];
+        for (row, enemies) in grid.iter_mut().enumerate() {
+            let enemy = match row {
+                0..=5 => Enemy::Normal,
+                6..=8 => Enemy::Sniper,
+                9..=10 => Enemy::Weaver,
+                11..=12 => Enemy::Seeker,
+                _ => Enemy::Lancer,
+            };
+            *enemies = [Some(enemy); ENEMY_COLS];
+        }
+
+        Self {
+            name: "Endless".to_string(),
+            grid,
+            shields: (0..5)
+                .map(|i| Vec2::new(i as f32 * 100. - 250., -75.))
+                .collect(),
+            march: None,
+            speed: 1.,
+            fire_rate: 1.,
+            waves: BOSS_WAVE_INTERVAL - 1,
+            boss_hp: Enemy::Boss.health(),
+            boss_style: BossStyle::default(),
+            background: ClearColor::default().0,
+        }
+    }
+}
+
+impl Level {
+    pub fn enemy_count(&self) -> usize {
+        self.grid.iter().flatten().flatten().count()
+    }
+
+    pub(super) fn march_style(&self, wave: u32) -> MarchStyle {
+        self.march.unwrap_or_else(|| MarchStyle::for_wave(wave))
+    }
+
+    pub fn march_name(&self) -> &'static str {
+        match self.march {
+            None => "alternate",
+            Some(MarchStyle::Smooth) => "smooth",
+            Some(MarchStyle::Stepped) => "stepped",
+        }
+    }
+
+    /// Unknown or malformed lines are skipped, and out of range parameters
+    /// are clamped. `row` lines go from the back of the formation to the
+    /// front.
+    fn parse(contents: &str) -> Self {
+        let mut level = Self {
+            grid: [[None; ENEMY_COLS]; ENEMY_ROWS],
+            shields: Vec::new(),
+            ..default()
+        };
+        let mut rows = Vec::new();
+        for line in contents.lines() {
+            let Some((key, value)) = line.trim().split_once(' ') else {
+                continue;
+            };
+            let value = value.trim();
+            match key {
+                "name" => level.name = value.to_string(),
+                "row" => rows.push(value),
+                "shield" => {
+                    if let Some((x, y)) = value.split_once(' ')
+                        && let (Ok(x), Ok(y)) = (x.trim().parse(), y.trim().parse())
+                    {
+                        level.shields.push(Vec2::new(x, y));
+                    }
+                }
+                "march" => {
+                    level.march = match value {
+                        "smooth" => Some(MarchStyle::Smooth),
+                        "stepped" => Some(MarchStyle::Stepped),
+                        _ => None,
+                    };
+                }
+                "speed" => level.speed = parameter(value).unwrap_or(1.),
+                "fire_rate" => level.fire_rate = parameter(value).unwrap_or(1.),
+                "waves" => {
+                    level.waves = value
+                        .parse()
+                        .ok()
+                        .filter(|&waves| waves >= 1)
+                        .unwrap_or(level.waves);
+                }
+                "boss" => {
+                    level.boss_hp = value
+                        .parse()
+                        .map_or(level.boss_hp, |hp: u32| hp.clamp(1, MAX_BOSS_HP));
+                }
+                "boss_style" => {
+                    level.boss_style = BossStyle::ALL
+                        .into_iter()
+                        .find(|style| style.id() == value)
+                        .unwrap_or(level.boss_style);
+                }
+                "background" => {
+                    let channels: Vec<f32> = value
+                        .split_whitespace()
+                        .filter_map(|channel| channel.parse().ok())
+                        .collect();
+                    if let [red, green, blue] = channels[..] {
+                        level.background = Color::srgb(red, green, blue);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (row, line) in rows.iter().rev().take(ENEMY_ROWS).enumerate() {
+            for (col, letter) in line.chars().take(ENEMY_COLS).enumerate() {
+                level.grid[row][col] = LETTERS
+                    .iter()
+                    .find(|(l, _)| *l == letter)
+                    .map(|&(_, enemy)| enemy);
+            }
+        }
+        level
+    }
+
+    /// Saves over the endless level.
+    pub fn save(&self) -> io::Result<()> {
+        let path = FileAssetReader::get_base_path()
+            .join("assets")
+            .join(LEVEL_PATH);
+        path.parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&path, self.contents()))
+    }
+
+    /// The level file `parse` reads back.
+    fn contents(&self) -> String {
+        let mut contents = String::from(
+            "# Rows go from the back of the formation to the front, one letter per\n\
+             # column: N normal, S sniper, W weaver, H seeker, L lancer, . empty\n",
+        );
+        let background = self.background.to_srgba();
+        contents += &format!(
+            "name {}\nmarch {}\nspeed {}\nfire_rate {}\nwaves {}\nboss {}\nboss_style {}\n\
+             background {} {} {}\n",
+            self.name,
+            self.march_name(),
+            self.speed,
+            self.fire_rate,
+            self.waves,
+            self.boss_hp,
+            self.boss_style.id(),
+            background.red,
+            background.green,
+            background.blue
+        );
+        for enemies in self.grid.iter().rev() {
+            let row: String = enemies
+                .iter()
+                .map(|enemy| match enemy {
+                    Some(enemy) => LETTERS
+                        .iter()
+                        .find(|(_, e)| e == enemy)
+                        .map_or('.', |&(letter, _)| letter),
+                    None => '.',
+                })
+                .collect();
+            contents += &format!("row {row}\n");
+        }
+        for shield in &self.shields {
+            contents += &format!("shield {} {}\n", shield.x, shield.y);
+        }
+        contents
+    }
+}
+
+/// A speed or fire rate, which has to be a finite number.
+fn parameter(value: &str) -> Option<f32> {
+    value
+        .parse::<f32>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .map(|value| value.clamp(MIN_PARAMETER, MAX_PARAMETER))
+}
+
+#[derive(Default)]
+struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = Level;
+    type Settings = ();
+    type Error = io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Level, io::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let contents = String::from_utf8(bytes)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        Ok(Level::parse(&contents))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level"]
+    }
+}
+
+fn load_levels(mut commands: Commands, assets: Res<GameAssets>, levels: Res<Assets<Level>>) {
+    // A level without enemies would clear its waves as soon as they start,
+    // so the classic formation stands in for it
+    let load = |handle: &Handle<Level>| match levels.get(handle) {
+        Some(level) if level.enemy_count() > 0 => level.clone(),
+        _ => {
+            let path = handle.path().map(ToString::to_string).unwrap_or_default();
+            warn!("No enemies in {path}, using the default formation");
+            Level::default()
+        }
+    };
+    commands.insert_resource(Levels {
+        endless: load(&assets.level),
+        stages: assets.stage_levels.iter().map(load).collect(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut level = Level {
+            name: "Test".to_string(),
+            march: Some(MarchStyle::Stepped),
+            speed: 1.5,
+            fire_rate: 0.5,
+            waves: 3,
+            boss_hp: 40,
+            boss_style: BossStyle::Sentinel,
+            background: Color::srgb(0.5, 0.25, 0.),
+            ..default()
+        };
+        level.grid[0][0] = None;
+        level.grid[14][9] = Some(Enemy::Weaver);
+
+        let parsed = Level::parse(&level.contents());
+        assert_eq!(parsed.name, level.name);
+        assert_eq!(parsed.grid, level.grid);
+        assert_eq!(parsed.shields, level.shields);
+        assert_eq!(parsed.march_name(), level.march_name());
+        assert_eq!(parsed.speed, level.speed);
+        assert_eq!(parsed.fire_rate, level.fire_rate);
+        assert_eq!(parsed.waves, level.waves);
+        assert_eq!(parsed.boss_hp, level.boss_hp);
+        assert_eq!(parsed.boss_style, level.boss_style);
+        assert_eq!(parsed.background, level.background);
+    }
+
+    #[test]
+    fn parameters_are_clamped() {
+        let level = Level::parse("speed 100\nfire_rate 0\nwaves 0\nboss 0\n");
+        assert_eq!(level.speed, MAX_PARAMETER);
+        assert_eq!(level.fire_rate, MIN_PARAMETER);
+        assert_eq!(level.waves, Level::default().waves);
+        assert_eq!(level.boss_hp, 1);
+
+        let level = Level::parse("boss 2000000000\n");
+        assert_eq!(level.boss_hp, MAX_BOSS_HP);
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let level = Level::parse(
+            "speed inf\nfire_rate NaN\nwaves -1\nboss lots\nboss_style giant\nshield 1\nbackground 1 2\nrow NX.\nnonsense\n",
+        );
+        let default = Level::default();
+        assert_eq!(level.speed, 1.);
+        assert_eq!(level.fire_rate, 1.);
+        assert_eq!(level.waves, default.waves);
+        assert_eq!(level.boss_hp, default.boss_hp);
+        assert_eq!(level.boss_style, default.boss_style);
+        assert!(level.shields.is_empty());
+        assert_eq!(level.background, default.background);
+        assert_eq!(level.grid[0][..3], [Some(Enemy::Normal), None, None]);
+        assert_eq!(level.enemy_count(), 1);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::GameState;
+
+use super::Bullet;
+use super::Hp;
+use super::MyRng;
+use super::OnGameScreen;
+use super::PLAYER_START;
+use super::Player;
+use super::PlayerHit;
+use super::mode::OutOfLives;
+use super::spawn_player;
+use super::versus::lockstep_ready;
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use rand::prelude::*;
+
+const RESPAWN_DELAY: f32 = 2.0;
+const INVULNERABILITY_TIME: f32 = 2.5;
+const BLINK_INTERVAL: f32 = 0.1;
+/// Enemy bullets within this distance of a destroyed ship are cleared, so the
+/// respawned ship doesn't fly straight into them.
+const CLEAR_RADIUS: f32 = 200.;
+
+const EXPLOSION_PARTICLES: usize = 24;
+const EXPLOSION_SPEED: f32 = 150.;
+const EXPLOSION_TIME: f32 = 0.8;
+
+pub fn lives_plugin(app: &mut App) {
+    app.register_type::<Invulnerable>()
+        .register_type::<Particle>()
+        .register_type::<Respawn>()
+        .add_systems(
+            FixedUpdate,
+            (respawn_player, update_invulnerability, update_particles)
+                .run_if(in_state(GameState::Running).and(lockstep_ready)),
+        )
+        .add_systems(
+            FixedPostUpdate,
+            destroy_player.run_if(in_state(GameState::Running)),
+        )
+        .add_systems(OnExit(GameState::Running), remove_respawn);
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct Respawn(pub Timer);
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Invulnerable(Timer);
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Particle {
+    velocity: Vec2,
+    lifetime: Timer,
+}
+
+impl Particle {
+    pub fn new(velocity: Vec2, lifetime: f32) -> Self {
+        Self {
+            velocity,
+            lifetime: Timer::from_seconds(lifetime, TimerMode::Once),
+        }
+    }
+}
+
+pub fn destroy_player(
+    mut commands: Commands,
+    mut event_reader: EventReader<PlayerHit>,
+    bullet_query: Query<(Entity, &Transform, &Bullet)>,
+    mut rng: ResMut<MyRng>,
+) {
+    for &PlayerHit { position } in event_reader.read() {
+        // Explosion
+        for i in 0..EXPLOSION_PARTICLES {
+            let angle = i as f32 / EXPLOSION_PARTICLES as f32 * TAU;
+            let speed = EXPLOSION_SPEED * rng.0.random_range(0.5..1.5);
+            commands.spawn((
+                Transform {
+                    translation: position,
+                    scale: Vec3::splat(rng.0.random_range(3. ..8.)),
+                    ..default()
+                },
+                Sprite {
+                    color: Color::srgb(1., rng.0.random_range(0.3..1.), 0.2),
+                    ..default()
+                },
+                Particle::new(Vec2::from_angle(angle) * speed, EXPLOSION_TIME),
+                OnGameScreen,
+            ));
+        }
+
+        // Clear nearby enemy bullets
+        for (entity, transform, bullet) in &bullet_query {
+            if matches!(bullet, Bullet::Enemy)
+                && transform.translation.distance(position) < CLEAR_RADIUS
+            {
+                commands.entity(entity).despawn();
+            }
+        }
+
+        commands.insert_resource(Respawn(Timer::from_seconds(RESPAWN_DELAY, TimerMode::Once)));
+    }
+}
+
+fn respawn_player(
+    mut commands: Commands,
+    respawn: Option<ResMut<Respawn>>,
+    hp: Res<Hp>,
+    time: Res<Time<Fixed>>,
+    mut lives_writer: EventWriter<OutOfLives>,
+) {
+    let Some(mut respawn) = respawn else {
+        return;
+    };
+
+    if !respawn.0.tick(time.delta()).finished() {
+        return;
+    }
+
+    commands.remove_resource::<Respawn>();
+
+    // The game mode decides what that means for the run
+    if hp.0 == 0 {
+        lives_writer.write(OutOfLives);
+        return;
+    }
+
+    let player = spawn_player(&mut commands, PLAYER_START);
+    commands
+        .entity(player)
+        .insert(Invulnerable(Timer::from_seconds(
+            INVULNERABILITY_TIME,
+            TimerMode::Once,
+        )));
+}
+
+fn update_invulnerability(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Invulnerable, &mut Visibility), With<Player>>,
+    time: Res<Time<Fixed>>,
+) {
+    for (entity, mut invulnerable, mut visibility) in &mut query {
+        if invulnerable.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Invulnerable>();
+            *visibility = Visibility::Inherited;
+            continue;
+        }
+
+        let blink = (invulnerable.0.elapsed_secs() / BLINK_INTERVAL) as u32;
+        *visibility = if blink.is_multiple_of(2) {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}
+
+fn update_particles(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Transform, &mut Sprite, &mut Particle)>,
+    time: Res<Time<Fixed>>,
+) {
+    for (entity, mut transform, mut sprite, mut particle) in &mut query {
+        if particle.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        transform.translation += (particle.velocity * time.delta_secs()).extend(0.);
+        sprite
+            .color
+            .set_alpha(particle.lifetime.fraction_remaining());
+    }
+}
+
+fn remove_respawn(mut commands: Commands) {
+    commands.remove_resource::<Respawn>();
+}
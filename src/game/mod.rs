@@ -1,12 +1,64 @@
+mod achievements;
+mod board;
+mod boss;
+mod broadphase;
+mod campaign;
+mod collision;
+mod combo;
+mod daily;
+#[cfg(feature = "debug")]
+mod debug;
+mod difficulty;
+mod director;
+mod dive;
+mod editor;
+mod feedback;
+mod hot_seat;
+mod level;
+mod lives;
+mod mode;
+mod projectile;
+mod save;
+mod snapshot;
+mod stats;
+mod sweep;
+mod targeting;
 mod ui;
+mod versus;
 
+pub use campaign::CampaignStage;
+pub use daily::{Challenge, DailyChallenge, DailyScores};
+pub use level::{Level, Levels};
+pub use save::SavedRun;
+pub use stats::RunStats;
+
+use crate::Difficulty;
 use crate::GameAssets;
+use crate::GameMode;
 use crate::GameState;
+use crate::Players;
 use crate::despawn_screen;
 
+use board::Board;
+use boss::{Boss, Laser, WeakPoint};
+use broadphase::Grid;
+use campaign::StageClear;
+use collision::{Collision, Layer};
+use combo::Combo;
+use difficulty::Preset;
+use director::Director;
+use dive::{Dive, slot_translation};
+use hot_seat::HotSeat;
+use lives::{Invulnerable, Particle};
+use projectile::Pattern;
+use targeting::TargetingPolicy;
+use versus::VersusState;
+
 use std::collections::HashMap;
+use std::f32::consts::TAU;
+use std::time::Duration;
 
-use bevy::math::bounding::{Aabb2d, BoundingVolume, IntersectsVolume};
+use bevy::math::bounding::{Aabb2d, BoundingVolume};
 use bevy::prelude::*;
 
 use rand::prelude::*;
@@ -16,107 +68,311 @@ const PLAYER_BULLET_SPEED: f32 = 800.;
 const ENEMY_BULLET_SPEED: f32 = 500.;
 const ENEMY_SPEED: f32 = 120.;
 const ENEMY_DROP: f32 = 20.;
+/// How many times faster the last enemy of a formation marches.
+const MARCH_SPEEDUP: f32 = 5.;
+const MARCH_STEP: f32 = 10.;
 
 const LEFT_WALL: f32 = -400.;
 const RIGHT_WALL: f32 = 400.;
 const BOTTOM_WALL: f32 = -300.;
 const TOP_WALL: f32 = 300.;
+/// How far past the side walls a bullet may fly before it's despawned.
+const BULLET_MARGIN: f32 = 200.;
 
 const PLAYER_FIRE_RATE: f32 = 10.0;
 const ENEMY_FIRE_RATE: f32 = 4.0;
 
-const STARTING_HP: u8 = 5;
+const MAX_HP: u8 = 8;
+const EXTRA_LIFE_SCORE: u32 = 1000;
+
+const PLAYER_START: Vec3 = Vec3::new(0., -250., 0.);
+
+const BOSS_WAVE_INTERVAL: u32 = 3;
 
-#[derive(Component)]
+const BROADPHASE_CELL_SIZE: f32 = 64.;
+
+const SHIELD_HITS: u32 = 5;
+
+const INTERCEPT_BONUS: u32 = 5;
+const SPARK_PARTICLES: usize = 6;
+const SPARK_SPEED: f32 = 120.;
+const SPARK_TIME: f32 = 0.3;
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 struct OnGameScreen;
 
 pub fn game_plugin(app: &mut App) {
-    app.add_plugins(ui::ui_plugin)
-        .init_resource::<InputState>()
-        .add_event::<EnemyKilled>()
-        .add_systems(OnEnter(GameState::Running), game_setup)
-        .add_systems(
-            FixedUpdate,
+    app.add_plugins((
+        ui::ui_plugin,
+        achievements::achievements_plugin,
+        boss::boss_plugin,
+        combo::combo_plugin,
+        director::director_plugin,
+        dive::dive_plugin,
+        feedback::feedback_plugin,
+        hot_seat::hot_seat_plugin,
+        lives::lives_plugin,
+        projectile::projectile_plugin,
+        save::save_plugin,
+        snapshot::snapshot_plugin,
+        stats::stats_plugin,
+        versus::versus_plugin,
+    ))
+    .add_plugins((
+        campaign::campaign_plugin,
+        daily::daily_plugin,
+        editor::editor_plugin,
+        level::level_plugin,
+        mode::mode_plugin,
+    ))
+    .init_resource::<InputState>()
+    .init_resource::<Broadphase>()
+    .register_type::<OnGameScreen>()
+    .register_type::<Player>()
+    .register_type::<Enemy>()
+    .register_type::<Health>()
+    .register_type::<Position>()
+    .register_type::<Shield>()
+    .register_type::<Bullet>()
+    .register_type::<Velocity>()
+    .register_type::<Heavy>()
+    .register_type::<Collider>()
+    .register_type::<PreviousPosition>()
+    .register_type::<collision::CollisionLayers>()
+    .register_type::<Score>()
+    .register_type::<Hp>()
+    .register_type::<Wave>()
+    .register_type::<FrontEnemies>()
+    .register_type::<EnemyDirection>()
+    .register_type::<MarchStyle>()
+    .register_type::<MarchProgress>()
+    .register_type::<PlayerFireTimer>()
+    .register_type::<EnemyFireTimer>()
+    .add_event::<EnemyKilled>()
+    .add_event::<PlayerHit>()
+    .add_event::<Collision>()
+    .add_event::<ShotMissed>()
+    .add_event::<WaveCleared>()
+    .add_systems(
+        OnEnter(GameState::Running),
+        // After the run's state starts over, so a resumed run can replace it
+        game_setup
+            .after(combo::setup_combo)
+            .after(director::setup_director)
+            .after(dive::setup_dives)
+            .after(stats::reset_stats),
+    )
+    .add_systems(
+        FixedUpdate,
+        (
+            // Player
+            player_movement,
+            player_fire,
+            // Enemies
+            enemy_movement,
+            enemy_fire,
+            // Bullets
+            bullet_movement,
+            // Game rules
+            update_score,
+            update_collider.after(bullet_movement),
+            update_front_enemies,
+            advance_wave,
+            rebuild_broadphase.after(update_collider),
+            collision::detect_collisions.after(rebuild_broadphase),
             (
-                // Player
-                player_movement,
-                player_fire,
-                // Enemies
-                enemy_movement,
-                enemy_fire,
-                // Bullets
-                bullet_movement,
-                // Game rules
-                update_score,
-                update_collider,
-                update_front_enemies,
-                enemy_bullet_collision,
-                shield_bullet_collision,
-                player_bullet_collision,
+                despawn_hit_bullets,
+                damage_enemies,
+                damage_shields,
+                damage_player,
+                intercept_bullets,
             )
-                .run_if(in_state(GameState::Running)),
+                .after(collision::detect_collisions),
         )
-        .add_systems(
-            Update,
-            (
-                // Input
-                update_player_direction,
-                update_player_fire,
-            )
-                .run_if(in_state(GameState::Running)),
+            .run_if(in_state(GameState::Running).and(versus::lockstep_ready)),
+    )
+    .add_systems(
+        Update,
+        (
+            // Input
+            update_player_direction,
+            update_player_fire,
         )
-        .add_systems(OnExit(GameState::Running), despawn_screen::<OnGameScreen>);
+            .run_if(in_state(GameState::Running)),
+    )
+    .add_systems(
+        OnExit(GameState::Running),
+        (despawn_screen::<OnGameScreen>, reset_background),
+    );
+
+    #[cfg(feature = "debug")]
+    app.add_plugins(debug::debug_plugin);
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 struct Player;
 
-#[derive(Component, Clone, Copy)]
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Reflect)]
+#[reflect(Component)]
 enum Enemy {
     Normal,
+    Sniper,
+    Weaver,
+    Seeker,
+    Lancer,
+    Minion,
+    Boss,
 }
 
 impl Enemy {
     fn points(self) -> u32 {
         match self {
             Enemy::Normal => 10,
+            Enemy::Sniper | Enemy::Weaver => 15,
+            Enemy::Seeker | Enemy::Minion => 20,
+            Enemy::Lancer => 25,
+            Enemy::Boss => 1000,
+        }
+    }
+
+    fn health(self) -> u32 {
+        match self {
+            Enemy::Normal | Enemy::Sniper | Enemy::Weaver | Enemy::Seeker | Enemy::Minion => 1,
+            Enemy::Lancer => 2,
+            Enemy::Boss => 150,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Enemy::Normal => "Normal",
+            Enemy::Sniper => "Sniper",
+            Enemy::Weaver => "Weaver",
+            Enemy::Seeker => "Seeker",
+            Enemy::Lancer => "Lancer",
+            Enemy::Minion => "Minion",
+            Enemy::Boss => "Boss",
+        }
+    }
+
+    /// How the enemy's shots fly when it fires from the formation.
+    fn pattern(self) -> Pattern {
+        match self {
+            Enemy::Normal | Enemy::Minion | Enemy::Boss => Pattern::Straight,
+            Enemy::Sniper => Pattern::Aimed,
+            Enemy::Weaver => Pattern::ZigZag,
+            Enemy::Seeker => Pattern::Homing,
+            Enemy::Lancer => Pattern::Beam,
         }
     }
 }
 
-#[derive(Component, Clone, Copy, Debug)]
+#[derive(Component, Clone, Copy, Reflect)]
+#[reflect(Component)]
+struct Health(u32);
+
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
 struct Position {
     row: usize,
     col: usize,
 }
 
-#[derive(Resource)]
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
 struct EnemyDirection(Direction);
 
-#[derive(Component)]
+/// How far a stepped march has got towards its next step.
+#[derive(Resource, Clone, Copy, Default, Reflect)]
+#[reflect(Resource)]
+struct MarchProgress(f32);
+
+/// How a level's formation marches.
+#[derive(Resource, Clone, Copy, Default, Reflect)]
+#[reflect(Resource)]
+enum MarchStyle {
+    #[default]
+    Smooth,
+    /// Jumps `MARCH_STEP` at a time, like the arcade original
+    Stepped,
+}
+
+impl MarchStyle {
+    fn for_wave(wave: u32) -> Self {
+        if wave.is_multiple_of(2) {
+            MarchStyle::Stepped
+        } else {
+            MarchStyle::Smooth
+        }
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 struct Collider(Aabb2d);
 
+/// Every `Collider` sorted into a grid, rebuilt each tick for the collision
+/// systems to look up nearby entities.
 #[derive(Resource)]
+struct Broadphase(Grid<Entity>);
+
+impl Default for Broadphase {
+    fn default() -> Self {
+        Self(Grid::new(BROADPHASE_CELL_SIZE))
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
 struct PlayerFireTimer(Timer);
 
-#[derive(Component)]
+#[derive(Component, Clone, Copy, Reflect)]
+#[reflect(Component)]
 struct Shield {
     hits: u32,
 }
 
-#[derive(Component)]
+#[derive(Component, Clone, Copy, Reflect)]
+#[reflect(Component)]
 enum Bullet {
     Player,
     Enemy,
 }
 
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct Velocity(Vec2);
+
+/// Enemy bullets that can't be shot down by the player.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct Heavy;
+
+/// Where a bullet was at the start of this tick. Fast bullets can skip
+/// right over a target in one tick, so collisions test the whole path.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct PreviousPosition(Vec2);
+
+impl PreviousPosition {
+    /// The box `aabb` started the tick as, and how far it moved since.
+    fn sweep_start(&self, aabb: &Aabb2d) -> (Aabb2d, Vec2) {
+        (
+            Aabb2d::new(self.0, aabb.half_size()),
+            aabb.center() - self.0,
+        )
+    }
+}
+
 #[derive(Resource, Default)]
 struct InputState {
     player_direction: Direction,
     player_fire: bool,
 }
 
-#[derive(Default, Clone, Copy)]
+#[derive(Default, Clone, Copy, Reflect)]
 enum Direction {
     Left,
     Right,
@@ -148,94 +404,67 @@ impl From<Direction> for f32 {
     }
 }
 
-#[derive(Resource)]
+#[derive(Resource, Clone)]
 struct MyRng(StdRng);
 
-fn game_setup(mut commands: Commands) {
-    // Player
-    commands.spawn((
-        Transform {
-            translation: Vec3::new(0., -250., 0.),
-            scale: Vec3::splat(30.),
-            ..default()
-        },
-        Sprite {
-            color: Color::srgb(0., 1., 0.5),
-            ..default()
-        },
-        Player,
-        Collider(Aabb2d::new(Vec2::default(), Vec2::splat(15.))),
-        OnGameScreen,
-    ));
-
-    // Enemies
-    let mut front_enemies = HashMap::new();
-
-    let rows = 15;
-    let cols = 10;
-    let spacing = 50.;
-    for col in 0..cols {
-        front_enemies.insert(col, 0);
-        for row in 0..rows {
-            let translation = Vec3::new(
-                col as f32 * spacing - (cols as f32 / 2.) * spacing,
-                row as f32 * spacing + 100.,
-                0.,
-            );
-            let scale = Vec3::splat(20.);
-            commands.spawn((
-                Transform {
-                    translation,
-                    scale,
-                    ..default()
-                },
-                Position { row, col },
-                Collider(Aabb2d::new(translation.truncate(), scale.truncate() / 2.)),
-                Sprite {
-                    color: Color::srgb(1., 0., 0.),
-                    ..default()
-                },
-                Enemy::Normal,
-                OnGameScreen,
-            ));
-        }
-    }
+#[allow(clippy::too_many_arguments)]
+fn game_setup(
+    mut commands: Commands,
+    players: Res<Players>,
+    difficulty: Res<Difficulty>,
+    mode: Res<GameMode>,
+    levels: Res<Levels>,
+    stage: Res<CampaignStage>,
+    daily: Res<DailyChallenge>,
+    saved_run: Option<Res<SavedRun>>,
+) {
+    // Difficulty
+    let preset = Preset::new(*difficulty, *mode);
+    commands.insert_resource(preset);
+    commands.insert_resource(preset.targeting);
 
-    commands.insert_resource(FrontEnemies(front_enemies));
+    // Mode
+    mode::setup_mode(&mut commands, *mode);
 
-    // HP
-    commands.insert_resource(Hp(STARTING_HP));
+    // Level. The daily challenge is always played on the classic formation,
+    // whatever the editor has done to the endless one.
+    let mut level = if daily.0.is_some() {
+        Level::default()
+    } else {
+        stage
+            .0
+            .and_then(|stage| levels.stages.get(stage))
+            .unwrap_or(&levels.endless)
+            .clone()
+    };
+    if let Some(challenge) = daily.0 {
+        if challenge.modifiers.no_shields {
+            level.shields.clear();
+        }
+        if challenge.modifiers.fast_enemies {
+            level.speed *= 2.;
+        }
+    }
+    commands.insert_resource(ClearColor(level.background));
+    commands.insert_resource(level.clone());
 
-    // Shields
-    let cols = 5;
-    let spacing = 100.;
-    for col in 0..cols {
-        let translation = Vec3::new(
-            col as f32 * spacing - (cols as f32 / 2.) * spacing,
-            -75.,
-            0.,
-        );
-        let scale = Vec3::new(30., 20., 0.);
-        commands.spawn((
-            Transform {
-                translation,
-                scale,
-                ..default()
-            },
-            Collider(Aabb2d::new(translation.truncate(), scale.truncate() / 2.)),
-            Sprite {
-                color: Color::srgb(0., 1., 0.5),
-                ..default()
-            },
-            Shield { hits: 0 },
-            OnGameScreen,
-        ));
+    // Resuming replaces everything below
+    if let Some(saved_run) = saved_run {
+        saved_run.restore(&mut commands, &level);
+        commands.remove_resource::<SavedRun>();
+        return;
     }
 
-    // Score
-    commands.insert_resource(Score(0));
+    // Player
+    spawn_player(&mut commands, PLAYER_START);
+
+    // Formation, shields, score and HP
+    Board::fresh(preset.starting_hp, &level).spawn(&mut commands, &level);
 
-    commands.insert_resource(EnemyDirection(Direction::Right));
+    // Hot-seat
+    if *players == Players::Two {
+        commands.insert_resource(HotSeat::new(preset.starting_hp, &level));
+    }
 
     // Fire timers
     commands.insert_resource(PlayerFireTimer(Timer::from_seconds(
@@ -247,13 +476,43 @@ fn game_setup(mut commands: Commands) {
         TimerMode::Repeating,
     )));
 
-    // RNG
-    commands.insert_resource(MyRng(StdRng::from_os_rng()));
+    // RNG, the same for everyone on a daily challenge
+    let rng = match daily.0 {
+        Some(challenge) => StdRng::seed_from_u64(challenge.seed),
+        None => StdRng::from_os_rng(),
+    };
+    commands.insert_resource(MyRng(rng));
+}
+
+fn reset_background(mut commands: Commands) {
+    commands.insert_resource(ClearColor::default());
+}
+
+fn spawn_player(commands: &mut Commands, translation: Vec3) -> Entity {
+    commands
+        .spawn((
+            Transform {
+                translation,
+                scale: Vec3::splat(30.),
+                ..default()
+            },
+            Sprite {
+                color: Color::srgb(0., 1., 0.5),
+                ..default()
+            },
+            Player,
+            Collider(Aabb2d::new(translation.truncate(), Vec2::splat(15.))),
+            collision::PLAYER,
+            OnGameScreen,
+        ))
+        .id()
 }
 
 #[derive(Event)]
 struct EnemyKilled {
-    position: Position,
+    /// `None` for enemies outside the formation, like bosses and minions
+    position: Option<Position>,
+    translation: Vec3,
     enemy: Enemy,
 }
 
@@ -262,11 +521,11 @@ fn update_front_enemies(
     mut front_enemies: ResMut<FrontEnemies>,
     mut event_reader: EventReader<EnemyKilled>,
 ) {
-    for &EnemyKilled {
-        position: Position { col, row },
-        ..
-    } in event_reader.read()
-    {
+    for &EnemyKilled { position, .. } in event_reader.read() {
+        let Some(Position { col, row }) = position else {
+            continue;
+        };
+
         let front_row = query
             .iter()
             // Exclude killed enemy if it's still present in this frame
@@ -284,14 +543,76 @@ fn update_front_enemies(
     }
 }
 
-#[derive(Resource)]
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
 struct Score(u32);
 
+impl Score {
+    /// Extra life every time a score threshold is crossed, unless the preset
+    /// doesn't allow them. Points scored after the last life is lost, like
+    /// a bullet already on its way, don't bring the player back.
+    fn add(&mut self, points: u32, hp: &mut Hp, preset: &Preset) {
+        let before = self.0;
+        self.0 += points;
+        if preset.extra_lives && hp.0 > 0 && self.0 / EXTRA_LIFE_SCORE > before / EXTRA_LIFE_SCORE {
+            hp.0 = (hp.0 + 1).min(MAX_HP);
+        }
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+struct Wave(u32);
+
+#[derive(Event)]
+struct WaveCleared;
+
+/// Campaign stages end after their boss wave, endless runs bring in a boss
+/// every few waves.
+fn advance_wave(
+    mut commands: Commands,
+    enemy_query: Query<(), With<Enemy>>,
+    mut wave: ResMut<Wave>,
+    mut event_writer: EventWriter<WaveCleared>,
+    level: Res<Level>,
+    stage: Res<CampaignStage>,
+    stage_clear: Option<Res<StageClear>>,
+) {
+    if !enemy_query.is_empty() || stage_clear.is_some() {
+        return;
+    }
+
+    event_writer.write(WaveCleared);
+    if stage.0.is_some() && wave.0 > level.waves {
+        commands.insert_resource(StageClear);
+        return;
+    }
+
+    wave.0 += 1;
+    commands.insert_resource(level.march_style(wave.0));
+    commands.insert_resource(MarchProgress::default());
+    let boss_wave = match stage.0 {
+        Some(_) => wave.0 > level.waves,
+        None => wave.0.is_multiple_of(BOSS_WAVE_INTERVAL),
+    };
+    if boss_wave {
+        boss::spawn_boss(
+            &mut commands,
+            level.boss_hp,
+            level.boss_hp,
+            level.boss_style,
+        );
+    } else {
+        board::spawn_formation(&mut commands, &level);
+    }
+}
+
 fn update_player_direction(
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    daily: Res<DailyChallenge>,
     mut input: ResMut<InputState>,
 ) {
-    input.player_direction = match (
+    let direction = match (
         keyboard_input.any_pressed([KeyCode::ArrowLeft, KeyCode::KeyA]),
         keyboard_input.any_pressed([KeyCode::ArrowRight, KeyCode::KeyD]),
     ) {
@@ -299,6 +620,14 @@ fn update_player_direction(
         (false, true) => Direction::Right,
         _ => Direction::None,
     };
+    let mirrored = daily
+        .0
+        .is_some_and(|challenge| challenge.modifiers.mirrored);
+    input.player_direction = if mirrored {
+        direction.flipped()
+    } else {
+        direction
+    };
 }
 
 fn update_player_fire(keyboard_input: Res<ButtonInput<KeyCode>>, mut input: ResMut<InputState>) {
@@ -331,9 +660,21 @@ fn player_fire(
         && fire_timer.0.finished()
         && let Ok(transform) = query.single()
     {
-        let translation = transform.translation + Vec3::new(0., 15., 0.);
-        let scale = Vec3::splat(5.);
+        spawn_player_bullet(
+            &mut commands,
+            transform.translation + Vec3::new(0., 15., 0.),
+        );
         commands.spawn((
+            AudioPlayer::new(assets.sound_shoot.clone()),
+            PlaybackSettings::DESPAWN.with_volume(bevy::audio::Volume::Linear(0.5)),
+        ));
+    }
+}
+
+fn spawn_player_bullet(commands: &mut Commands, translation: Vec3) -> Entity {
+    let scale = Vec3::splat(5.);
+    commands
+        .spawn((
             Transform {
                 translation,
                 scale,
@@ -344,47 +685,86 @@ fn player_fire(
                 ..default()
             },
             Bullet::Player,
+            Velocity(Vec2::Y * PLAYER_BULLET_SPEED),
             Collider(Aabb2d::new(translation.truncate(), scale.truncate() / 2.)),
+            PreviousPosition(translation.truncate()),
+            collision::PLAYER_BULLET,
             OnGameScreen,
-        ));
-        commands.spawn((
-            AudioPlayer::new(assets.sound_shoot.clone()),
-            PlaybackSettings::DESPAWN.with_volume(bevy::audio::Volume::Linear(0.5)),
-        ));
-    }
+        ))
+        .id()
 }
 
-#[derive(Resource)]
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
 struct FrontEnemies(HashMap<usize, usize>);
 
-#[derive(Resource)]
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
 struct EnemyFireTimer(Timer);
 
+#[allow(clippy::too_many_arguments)]
 fn enemy_fire(
     time: Res<Time<Fixed>>,
     mut fire_timer: ResMut<EnemyFireTimer>,
     mut commands: Commands,
-    query: Query<(&Transform, &Position), With<Enemy>>,
+    query: Query<(&Transform, &Position, &Enemy)>,
+    player_query: Query<&Transform, With<Player>>,
+    bullet_query: Query<&Bullet>,
     front_enemies: Res<FrontEnemies>,
+    policy: Res<TargetingPolicy>,
+    director: Res<Director>,
+    wave: Res<Wave>,
+    level: Res<Level>,
     mut rng: ResMut<MyRng>,
 ) {
-    fire_timer.0.tick(time.delta());
+    // Later waves fire more often
+    let fire_rate = policy.fire_rate(wave.0) * director.fire_scale * level.fire_rate;
+    let duration = Duration::from_secs_f32(1. / fire_rate);
+    if fire_timer.0.duration() != duration {
+        fire_timer.0.set_duration(duration);
+    }
 
-    if fire_timer.0.finished()
-        && let Some(transform) = query
-            .iter()
-            .filter_map(|(transform, Position { row, col })| {
-                front_enemies
-                    .0
-                    .get(col)
-                    .is_some_and(|front_row| row == front_row)
-                    .then_some(transform)
-            })
-            .choose(&mut rng.0)
-    {
-        let translation = transform.translation - Vec3::new(0., 15., 0.);
-        let scale = Vec3::splat(5.);
-        commands.spawn((
+    if !fire_timer.0.tick(time.delta()).finished() {
+        return;
+    }
+
+    let candidates: Vec<_> = query
+        .iter()
+        .filter_map(|(transform, Position { row, col }, &enemy)| {
+            front_enemies
+                .0
+                .get(col)
+                .is_some_and(|front_row| row == front_row)
+                .then_some((transform.translation, enemy))
+        })
+        .collect();
+    let bullets = bullet_query
+        .iter()
+        .filter(|bullet| matches!(bullet, Bullet::Enemy))
+        .count();
+    let player = player_query.single().ok();
+
+    let shooters = policy.choose_shooters(
+        &candidates,
+        player.map(|player| player.translation.x),
+        bullets,
+        &mut rng.0,
+    );
+    let target = player.map_or(PLAYER_START, |player| player.translation);
+    for (translation, enemy) in shooters {
+        projectile::fire(
+            &mut commands,
+            enemy.pattern(),
+            translation - Vec3::new(0., 15., 0.),
+            target,
+        );
+    }
+}
+
+fn spawn_enemy_bullet(commands: &mut Commands, translation: Vec3, velocity: Vec2) -> Entity {
+    let scale = Vec3::splat(5.);
+    commands
+        .spawn((
             Transform {
                 translation,
                 scale,
@@ -395,46 +775,97 @@ fn enemy_fire(
                 ..default()
             },
             Collider(Aabb2d::new(translation.truncate(), scale.truncate() / 2.)),
+            PreviousPosition(translation.truncate()),
+            collision::ENEMY_BULLET,
             Bullet::Enemy,
+            Velocity(velocity),
             OnGameScreen,
-        ));
-    }
+        ))
+        .id()
 }
 
+/// A player bullet left the screen without hitting anything.
+#[derive(Event)]
+struct ShotMissed;
+
 fn bullet_movement(
     mut commands: Commands,
-    mut query: Query<(Entity, &mut Transform, &Bullet)>,
+    mut event_writer: EventWriter<ShotMissed>,
+    mut query: Query<(
+        Entity,
+        &mut Transform,
+        &mut PreviousPosition,
+        &Velocity,
+        &Bullet,
+    )>,
+    preset: Res<Preset>,
     time: Res<Time<Fixed>>,
 ) {
-    for (entity, mut transform, bullet) in &mut query {
+    for (entity, mut transform, mut previous, Velocity(velocity), bullet) in &mut query {
         let speed = match bullet {
-            Bullet::Player => PLAYER_BULLET_SPEED,
-            Bullet::Enemy => -ENEMY_BULLET_SPEED,
+            Bullet::Player => 1.,
+            Bullet::Enemy => preset.enemy_bullet_speed,
         };
-        transform.translation.y += speed * time.delta_secs();
-        if !(BOTTOM_WALL..=TOP_WALL).contains(&transform.translation.y) {
+        previous.0 = transform.translation.truncate();
+        transform.translation += (velocity * speed * time.delta_secs()).extend(0.);
+        if !(BOTTOM_WALL..=TOP_WALL).contains(&transform.translation.y)
+            || transform.translation.x.abs() > RIGHT_WALL + BULLET_MARGIN
+        {
             commands.entity(entity).despawn();
+            if matches!(bullet, Bullet::Player) {
+                event_writer.write(ShotMissed);
+            }
         }
     }
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn enemy_movement(
-    mut query: Query<&mut Transform, With<Enemy>>,
+    mut query: Query<(&mut Transform, Option<&mut Dive>), (With<Enemy>, With<Position>)>,
     mut direction: ResMut<EnemyDirection>,
+    march_style: Res<MarchStyle>,
+    preset: Res<Preset>,
+    director: Res<Director>,
+    level: Res<Level>,
     time: Res<Time<Fixed>>,
+    mut progress: ResMut<MarchProgress>,
 ) {
     let direction_f32 = f32::from(direction.0);
 
-    let move_down = query.iter().any(|transform| {
-        let new_x = transform.translation.x + direction_f32 * ENEMY_SPEED * time.delta_secs();
+    // The fewer enemies are left, the faster they march
+    let killed = 1. - query.iter().count() as f32 / level.enemy_count().max(1) as f32;
+    let speedup = 1. + (MARCH_SPEEDUP - 1.) * killed.max(0.).powi(2);
+    let speed = ENEMY_SPEED * preset.enemy_speed * director.speed_scale * level.speed * speedup;
+
+    let distance = match *march_style {
+        MarchStyle::Smooth => speed * time.delta_secs(),
+        MarchStyle::Stepped => {
+            progress.0 += speed * time.delta_secs();
+            let steps = (progress.0 / MARCH_STEP).floor();
+            if steps == 0. {
+                return;
+            }
+            progress.0 -= steps * MARCH_STEP;
+            steps * MARCH_STEP
+        }
+    };
+
+    let move_down = query.iter().any(|(transform, dive)| {
+        let new_x = slot_translation(transform, dive).x + direction_f32 * distance;
         !(LEFT_WALL..=RIGHT_WALL).contains(&new_x)
     });
 
-    for mut transform in &mut query {
+    for (transform, dive) in &mut query {
+        // Divers have left the formation, but their slot keeps marching
+        let translation = match dive {
+            Some(dive) => &mut dive.into_inner().slot,
+            None => &mut transform.into_inner().translation,
+        };
+
         if move_down {
-            transform.translation.y -= ENEMY_DROP;
+            translation.y -= ENEMY_DROP;
         } else {
-            transform.translation.x += direction_f32 * ENEMY_SPEED * time.delta_secs();
+            translation.x += direction_f32 * distance;
         }
     }
 
@@ -450,81 +881,221 @@ fn update_collider(mut query: Query<(&Transform, &mut Collider), Changed<Transfo
     }
 }
 
-fn enemy_bullet_collision(
+fn rebuild_broadphase(
+    mut broadphase: ResMut<Broadphase>,
+    query: Query<(Entity, &Collider, Option<&PreviousPosition>)>,
+) {
+    broadphase.0.clear();
+    for (entity, &Collider(aabb), previous) in &query {
+        // Bullets cover everything they passed through this tick
+        let aabb = match previous {
+            Some(previous) => aabb.merge(&previous.sweep_start(&aabb).0),
+            None => aabb,
+        };
+        broadphase.0.insert(aabb, entity);
+    }
+}
+
+/// Bullets are used up by whatever they hit first.
+fn despawn_hit_bullets(
     mut commands: Commands,
-    bullet_query: Query<(Entity, &Collider, &Bullet)>,
-    enemy_query: Query<(Entity, &Collider, &Position, &Enemy)>,
+    mut event_reader: EventReader<Collision>,
+    bullet_query: Query<(), With<Bullet>>,
+) {
+    for collision in event_reader.read() {
+        if bullet_query.contains(collision.entity) {
+            commands.entity(collision.entity).despawn();
+        }
+    }
+}
+
+fn damage_enemies(
+    mut commands: Commands,
+    mut collision_reader: EventReader<Collision>,
+    weak_point_query: Query<&WeakPoint>,
+    mut enemy_query: Query<(&mut Health, &Transform, &Enemy, Option<&Position>)>,
     mut event_writer: EventWriter<EnemyKilled>,
 ) {
-    for (bullet_entity, Collider(bullet_aabb), _) in bullet_query
-        .iter()
-        .filter(|(_, _, b)| !matches!(b, Bullet::Enemy))
-    {
-        for (enemy_entity, Collider(enemy_aabb), &position, &enemy_kind) in enemy_query {
-            if bullet_aabb.intersects(enemy_aabb) {
-                commands.entity(bullet_entity).despawn();
+    for collision in collision_reader.read() {
+        if collision.layer != Layer::Enemy {
+            continue;
+        }
+
+        // Weak points pass their extra damage on to the boss
+        let (enemy_entity, damage) = match weak_point_query.get(collision.other) {
+            Ok(weak_point) => (weak_point.boss, weak_point.damage),
+            Err(_) => (collision.other, 1),
+        };
+
+        if let Ok((mut health, transform, &enemy, position)) = enemy_query.get_mut(enemy_entity)
+            && health.0 > 0
+        {
+            health.0 = health.0.saturating_sub(damage);
+            if health.0 == 0 {
                 commands.entity(enemy_entity).despawn();
                 event_writer.write(EnemyKilled {
-                    position,
-                    enemy: enemy_kind,
+                    position: position.copied(),
+                    translation: transform.translation,
+                    enemy,
                 });
-                break;
+            } else {
+                feedback::flash(&mut commands, enemy_entity);
             }
         }
     }
 }
 
-fn update_score(mut event_reader: EventReader<EnemyKilled>, mut score: ResMut<Score>) {
-    for EnemyKilled { enemy, .. } in event_reader.read() {
-        score.0 += enemy.points();
+fn update_score(
+    mut commands: Commands,
+    mut event_reader: EventReader<EnemyKilled>,
+    mut score: ResMut<Score>,
+    mut hp: ResMut<Hp>,
+    preset: Res<Preset>,
+    combo: Res<Combo>,
+    assets: Res<GameAssets>,
+) {
+    for &EnemyKilled {
+        translation, enemy, ..
+    } in event_reader.read()
+    {
+        let base = preset.points(enemy.points());
+        let multiplier = combo.multiplier();
+        score.add(base * multiplier, &mut hp, &preset);
+
+        let text = match multiplier {
+            1 => format!("{base}"),
+            _ => format!("{base} x{multiplier}"),
+        };
+        feedback::spawn_popup(&mut commands, &assets, translation, text);
     }
 }
 
-fn player_bullet_collision(
+fn damage_player(
     mut commands: Commands,
-    bullet_query: Query<(Entity, &Collider, &Bullet)>,
-    player_query: Query<&Collider, With<Player>>,
+    mut collision_reader: EventReader<Collision>,
+    player_query: Query<&Transform, With<Player>>,
     mut hp: ResMut<Hp>,
-    mut state: ResMut<NextState<GameState>>,
+    mut event_writer: EventWriter<PlayerHit>,
 ) {
-    if let Ok(Collider(enemy_aabb)) = player_query.single() {
-        for (bullet_entity, Collider(bullet_aabb), bullet) in bullet_query {
-            if !matches!(bullet, Bullet::Enemy) {
-                continue;
-            }
+    // Several bullets may land in the same tick, but the player only dies once
+    if let Some(collision) = collision_reader
+        .read()
+        .filter(|collision| collision.layer == Layer::Player)
+        .last()
+        && let Ok(transform) = player_query.get(collision.other)
+    {
+        hit_player(
+            &mut commands,
+            collision.other,
+            transform.translation,
+            &mut hp,
+            &mut event_writer,
+        );
+    }
+}
 
-            if bullet_aabb.intersects(enemy_aabb) {
-                hp.0 -= 1;
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+struct Hp(u8);
 
-                if hp.0 == 0 {
-                    state.set(GameState::GameOver);
-                }
+#[derive(Event)]
+struct PlayerHit {
+    position: Vec3,
+}
 
-                commands.entity(bullet_entity).despawn();
-                break;
-            }
+fn hit_player(
+    commands: &mut Commands,
+    player: Entity,
+    position: Vec3,
+    hp: &mut Hp,
+    event_writer: &mut EventWriter<PlayerHit>,
+) {
+    hp.0 = hp.0.saturating_sub(1);
+    event_writer.write(PlayerHit { position });
+    commands.entity(player).despawn();
+}
+
+fn intercept_bullets(
+    mut commands: Commands,
+    mut collision_reader: EventReader<Collision>,
+    bullet_query: Query<(&Transform, Has<Heavy>), With<Bullet>>,
+    mut score: ResMut<Score>,
+    mut hp: ResMut<Hp>,
+    preset: Res<Preset>,
+    mut rng: ResMut<MyRng>,
+) {
+    let mut intercepted = Vec::new();
+    for collision in collision_reader.read() {
+        if collision.layer != Layer::EnemyBullet || intercepted.contains(&collision.other) {
+            continue;
+        }
+
+        let Ok((transform, false)) = bullet_query.get(collision.other) else {
+            continue;
+        };
+
+        intercepted.push(collision.other);
+        commands.entity(collision.other).despawn();
+        score.add(preset.points(INTERCEPT_BONUS), &mut hp, &preset);
+
+        // Sparks
+        for _ in 0..SPARK_PARTICLES {
+            let velocity = Vec2::from_angle(rng.0.random_range(0. ..TAU)) * SPARK_SPEED;
+            commands.spawn((
+                Transform {
+                    translation: transform.translation,
+                    scale: Vec3::splat(2.),
+                    ..default()
+                },
+                Sprite {
+                    color: Color::srgb(1., 1., 0.6),
+                    ..default()
+                },
+                Particle::new(velocity, SPARK_TIME),
+                OnGameScreen,
+            ));
         }
     }
 }
 
-#[derive(Resource)]
-struct Hp(u8);
-
-fn shield_bullet_collision(
+fn damage_shields(
     mut commands: Commands,
-    bullet_query: Query<(Entity, &Collider), With<Bullet>>,
-    mut shield_query: Query<(Entity, &Collider, &mut Shield)>,
+    mut collision_reader: EventReader<Collision>,
+    mut shield_query: Query<&mut Shield>,
+    mut stats: ResMut<RunStats>,
 ) {
-    for (bullet_entity, Collider(bullet_aabb)) in bullet_query {
-        for (shield_entity, Collider(shield_aabb), mut shield) in &mut shield_query {
-            if bullet_aabb.intersects(shield_aabb) {
-                commands.entity(bullet_entity).despawn();
-                shield.hits += 1;
-                if shield.hits >= 5 {
-                    commands.entity(shield_entity).despawn();
-                }
-                break;
+    for collision in collision_reader.read() {
+        if collision.layer == Layer::Shield
+            && let Ok(mut shield) = shield_query.get_mut(collision.other)
+        {
+            shield.hits += 1;
+            if shield.hits == SHIELD_HITS {
+                commands.entity(collision.other).despawn();
+                stats.shields_lost += 1;
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extra_lives_need_a_life_left() {
+        let mut preset = Preset::new(Difficulty::Normal, GameMode::Standard);
+        preset.extra_lives = true;
+
+        let mut score = Score(EXTRA_LIFE_SCORE - 10);
+        let mut hp = Hp(1);
+        score.add(20, &mut hp, &preset);
+        assert_eq!(hp.0, 2);
+
+        // Out of lives and waiting to respawn
+        let mut score = Score(EXTRA_LIFE_SCORE - 10);
+        let mut hp = Hp(0);
+        score.add(20, &mut hp, &preset);
+        assert_eq!(hp.0, 0);
+        assert_eq!(score.0, EXTRA_LIFE_SCORE + 10);
+    }
+}
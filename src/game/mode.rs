@@ -0,0 +1,176 @@
+//! How a run ends, as the game mode decides: Time Attack also ends when the
+//! clock runs out, and Survival brings killed enemies back into the
+//! formation. One-Life only changes the preset.
+
+use crate::GameMode;
+use crate::GameState;
+use crate::profile::Profile;
+
+use super::CampaignStage;
+use super::DailyChallenge;
+use super::Dive;
+use super::Enemy;
+use super::FrontEnemies;
+use super::Level;
+use super::MyRng;
+use super::Position;
+use super::RunStats;
+use super::board::{ENEMY_COLS, ENEMY_ROWS, ENEMY_SPACING, spawn_enemy};
+use super::slot_translation;
+use super::stats::Outcome;
+use super::versus::lockstep_ready;
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use rand::prelude::*;
+
+const TIME_ATTACK_LIMIT: f32 = 120.;
+/// How often Survival brings a killed enemy back.
+const REINFORCEMENT_INTERVAL: f32 = 1.5;
+
+pub fn mode_plugin(app: &mut App) {
+    app.register_type::<TimeLimit>()
+        .register_type::<Reinforcements>()
+        .add_event::<OutOfLives>()
+        .add_systems(
+            FixedUpdate,
+            (
+                tick_time_limit.run_if(resource_exists::<TimeLimit>),
+                reinforce.run_if(resource_exists::<Reinforcements>),
+                end_run,
+            )
+                .chain()
+                .run_if(in_state(GameState::Running).and(lockstep_ready)),
+        )
+        .add_systems(OnEnter(GameState::GameOver), record_mode_score)
+        .add_systems(OnExit(GameState::Running), remove_mode);
+}
+
+/// Written once the last life is lost and the explosion has played out.
+#[derive(Event)]
+pub struct OutOfLives;
+
+/// Time left in a Time Attack run.
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct TimeLimit(pub Timer);
+
+impl TimeLimit {
+    pub fn new(elapsed: f32) -> Self {
+        let mut timer = Timer::from_seconds(TIME_ATTACK_LIMIT, TimerMode::Once);
+        timer.set_elapsed(Duration::try_from_secs_f32(elapsed).unwrap_or_default());
+        Self(timer)
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct Reinforcements(Timer);
+
+/// Inserts what the mode needs on top of the usual game setup.
+pub fn setup_mode(commands: &mut Commands, mode: GameMode) {
+    match mode {
+        GameMode::TimeAttack => commands.insert_resource(TimeLimit::new(0.)),
+        GameMode::Survival => commands.insert_resource(Reinforcements(Timer::from_seconds(
+            REINFORCEMENT_INTERVAL,
+            TimerMode::Repeating,
+        ))),
+        GameMode::Standard | GameMode::OneLife => {}
+    }
+}
+
+fn tick_time_limit(mut time_limit: ResMut<TimeLimit>, time: Res<Time<Fixed>>) {
+    time_limit.0.tick(time.delta());
+}
+
+/// Fills an empty slot of the level's formation with the enemy it started
+/// with.
+#[allow(clippy::type_complexity)]
+fn reinforce(
+    mut commands: Commands,
+    mut reinforcements: ResMut<Reinforcements>,
+    time: Res<Time<Fixed>>,
+    enemy_query: Query<(&Transform, &Position, Option<&Dive>), With<Enemy>>,
+    mut front_enemies: ResMut<FrontEnemies>,
+    level: Res<Level>,
+    mut rng: ResMut<MyRng>,
+) {
+    if !reinforcements.0.tick(time.delta()).finished() {
+        return;
+    }
+
+    // Slots line up with any enemy still in the formation. Without one, the
+    // next wave is on its way anyway.
+    let Some((transform, anchor, dive)) = enemy_query.iter().next() else {
+        return;
+    };
+    let origin = slot_translation(transform, dive)
+        - Vec3::new(anchor.col as f32, anchor.row as f32, 0.) * ENEMY_SPACING;
+
+    let occupied: HashSet<_> = enemy_query
+        .iter()
+        .map(|(_, position, _)| (position.row, position.col))
+        .collect();
+    let empty: Vec<_> = (0..ENEMY_ROWS)
+        .flat_map(|row| (0..ENEMY_COLS).map(move |col| (row, col)))
+        .filter(|&(row, col)| level.grid[row][col].is_some() && !occupied.contains(&(row, col)))
+        .collect();
+    let Some(&(row, col)) = empty.choose(&mut rng.0) else {
+        return;
+    };
+    let Some(enemy) = level.grid[row][col] else {
+        return;
+    };
+
+    let translation = origin + Vec3::new(col as f32, row as f32, 0.) * ENEMY_SPACING;
+    spawn_enemy(&mut commands, translation, Position { row, col }, enemy);
+    front_enemies
+        .0
+        .entry(col)
+        .and_modify(|front| *front = (*front).min(row))
+        .or_insert(row);
+}
+
+/// Running out of lives ends every mode, Time Attack also ends when the
+/// clock does.
+fn end_run(
+    mode: Res<GameMode>,
+    mut lives_reader: EventReader<OutOfLives>,
+    time_limit: Option<Res<TimeLimit>>,
+    mut stats: ResMut<RunStats>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    let out_of_lives = lives_reader.read().count() > 0;
+    let out_of_time = time_limit.is_some_and(|time_limit| time_limit.0.finished());
+    let over = match *mode {
+        GameMode::TimeAttack => out_of_lives || out_of_time,
+        GameMode::Standard | GameMode::Survival | GameMode::OneLife => out_of_lives,
+    };
+    if over {
+        if !out_of_lives {
+            stats.outcome = Outcome::TimeUp;
+        }
+        state.set(GameState::GameOver);
+    }
+}
+
+/// Campaign stages and daily challenges keep their own bests.
+fn record_mode_score(
+    stage: Res<CampaignStage>,
+    daily: Res<DailyChallenge>,
+    stats: Res<RunStats>,
+    mut profile: ResMut<Profile>,
+) {
+    if stage.0.is_none() && daily.0.is_none() {
+        profile.record_mode_score(stats.mode, stats.difficulty, stats.score);
+        profile.save();
+    }
+}
+
+fn remove_mode(mut commands: Commands) {
+    commands.remove_resource::<TimeLimit>();
+    commands.remove_resource::<Reinforcements>();
+}
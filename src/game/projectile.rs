@@ -0,0 +1,134 @@
+use crate::GameState;
+
+use super::Collider;
+use super::ENEMY_BULLET_SPEED;
+use super::Heavy;
+use super::Player;
+use super::Velocity;
+use super::bullet_movement;
+use super::spawn_enemy_bullet;
+use super::versus::lockstep_ready;
+
+use std::time::Duration;
+
+use bevy::math::bounding::Aabb2d;
+use bevy::prelude::*;
+
+const ZIG_ZAG_INTERVAL: f32 = 0.25;
+const ZIG_ZAG_SWAY: f32 = 200.;
+
+const HOMING_SPEED: f32 = 220.;
+/// How fast homing shots turn towards the player, in radians per second.
+const HOMING_TURN_RATE: f32 = 1.2;
+
+const BEAM_SPEED: f32 = 260.;
+const BEAM_SIZE: Vec2 = Vec2::new(40., 6.);
+
+pub fn projectile_plugin(app: &mut App) {
+    app.register_type::<ZigZag>()
+        .register_type::<Homing>()
+        .add_systems(
+            FixedUpdate,
+            (zig_zag, home_in)
+                .before(bullet_movement)
+                .run_if(in_state(GameState::Running).and(lockstep_ready)),
+        );
+}
+
+/// How an enemy's shots fly.
+#[derive(Clone, Copy)]
+pub enum Pattern {
+    Straight,
+    /// Fired straight at the player
+    Aimed,
+    /// Falls while swaying from side to side
+    ZigZag,
+    /// Slowly turns to follow the player
+    Homing,
+    /// A wide, slow shot that can't be shot down
+    Beam,
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct ZigZag(pub Timer);
+
+impl ZigZag {
+    /// `elapsed` into the current sway.
+    pub fn new(elapsed: f32) -> Self {
+        let mut timer = Timer::from_seconds(ZIG_ZAG_INTERVAL, TimerMode::Repeating);
+        timer.set_elapsed(Duration::try_from_secs_f32(elapsed).unwrap_or_default());
+        Self(timer)
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Homing;
+
+/// Fires one shot from `origin`, aimed at `target` if the pattern aims.
+pub fn fire(commands: &mut Commands, pattern: Pattern, origin: Vec3, target: Vec3) {
+    let aim = (target - origin).truncate().normalize_or(Vec2::NEG_Y);
+    match pattern {
+        Pattern::Straight => {
+            spawn_enemy_bullet(commands, origin, Vec2::NEG_Y * ENEMY_BULLET_SPEED);
+        }
+        Pattern::Aimed => {
+            spawn_enemy_bullet(commands, origin, aim * ENEMY_BULLET_SPEED);
+        }
+        Pattern::ZigZag => {
+            let velocity = Vec2::new(ZIG_ZAG_SWAY, -ENEMY_BULLET_SPEED * 0.6);
+            let bullet = spawn_enemy_bullet(commands, origin, velocity);
+            // Start halfway through a sway so the shot stays centred on `origin`
+            commands
+                .entity(bullet)
+                .insert(ZigZag::new(ZIG_ZAG_INTERVAL / 2.));
+        }
+        Pattern::Homing => {
+            let bullet = spawn_enemy_bullet(commands, origin, aim * HOMING_SPEED);
+            commands.entity(bullet).insert(Homing);
+        }
+        Pattern::Beam => {
+            let bullet = spawn_enemy_bullet(commands, origin, Vec2::NEG_Y * BEAM_SPEED);
+            commands.entity(bullet).insert((
+                Transform {
+                    translation: origin,
+                    scale: BEAM_SIZE.extend(1.),
+                    ..default()
+                },
+                Collider(Aabb2d::new(origin.truncate(), BEAM_SIZE / 2.)),
+                Heavy,
+            ));
+        }
+    }
+}
+
+fn zig_zag(mut query: Query<(&mut Velocity, &mut ZigZag)>, time: Res<Time<Fixed>>) {
+    for (mut velocity, mut zig_zag) in &mut query {
+        if zig_zag.0.tick(time.delta()).just_finished() {
+            velocity.0.x = -velocity.0.x;
+        }
+    }
+}
+
+fn home_in(
+    mut query: Query<(&Transform, &mut Velocity), With<Homing>>,
+    player_query: Query<&Transform, With<Player>>,
+    time: Res<Time<Fixed>>,
+) {
+    let Ok(player) = player_query.single() else {
+        return;
+    };
+
+    for (transform, mut velocity) in &mut query {
+        let to_player = (player.translation - transform.translation).truncate();
+        // Once past the player, keep going instead of circling back
+        if to_player.y > 0. {
+            continue;
+        }
+
+        let angle = velocity.0.angle_to(to_player);
+        let max_turn = HOMING_TURN_RATE * time.delta_secs();
+        velocity.0 = Vec2::from_angle(angle.clamp(-max_turn, max_turn)).rotate(velocity.0);
+    }
+}
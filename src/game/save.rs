@@ -0,0 +1,787 @@
+//! Suspending a single-player run to a small text file of `key value` lines
+//! in the data directory, and resuming it from the menu.
+
+use crate::Difficulty;
+use crate::GameMode;
+use crate::GameState;
+use crate::Players;
+use crate::data_path;
+use crate::write_data_file;
+
+use super::Board;
+use super::Bullet;
+use super::CampaignStage;
+use super::Challenge;
+use super::Collider;
+use super::Combo;
+use super::DailyChallenge;
+use super::Direction;
+use super::Director;
+use super::Dive;
+use super::ENEMY_FIRE_RATE;
+use super::Enemy;
+use super::EnemyFireTimer;
+use super::Heavy;
+use super::Level;
+use super::MyRng;
+use super::PLAYER_FIRE_RATE;
+use super::PLAYER_START;
+use super::Player;
+use super::PlayerFireTimer;
+use super::Position;
+use super::RunStats;
+use super::Shield;
+use super::Velocity;
+use super::board::CurrentBoard;
+use super::combo::WaveStats;
+use super::director::{MAX_SCALE, MIN_SCALE};
+use super::dive::{DivePhase, DiveTimer};
+use super::editor::Playtest;
+use super::level::MAX_BOSS_HP;
+use super::mode::TimeLimit;
+use super::projectile::{Homing, ZigZag};
+use super::spawn_enemy_bullet;
+use super::spawn_player;
+use super::spawn_player_bullet;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::time::Duration;
+
+use bevy::ecs::system::SystemParam;
+use bevy::math::bounding::Aabb2d;
+use bevy::prelude::*;
+
+use rand::prelude::*;
+
+const SAVE_FILE: &str = "save.txt";
+
+const ENEMIES: [Enemy; 7] = [
+    Enemy::Normal,
+    Enemy::Sniper,
+    Enemy::Weaver,
+    Enemy::Seeker,
+    Enemy::Lancer,
+    Enemy::Minion,
+    Enemy::Boss,
+];
+
+pub fn save_plugin(app: &mut App) {
+    // After everything else, so a window closed this frame is seen
+    app.add_systems(
+        Last,
+        save_run.run_if(in_state(GameState::Running).and(not(resource_exists::<Playtest>))),
+    );
+}
+
+#[derive(Clone, Copy)]
+struct SavedBullet {
+    bullet: Bullet,
+    translation: Vec3,
+    velocity: Vec2,
+    size: Vec2,
+    heavy: bool,
+    homing: bool,
+    /// Time into the current sway of a zig-zagging bullet
+    zig_zag: Option<f32>,
+}
+
+/// A suspended run. The menu inserts it before entering `GameState::Running`
+/// to resume it instead of starting a fresh one.
+#[derive(Resource)]
+pub struct SavedRun {
+    pub difficulty: Difficulty,
+    pub mode: GameMode,
+    pub stage: CampaignStage,
+    pub daily: DailyChallenge,
+    board: Board,
+    player: Vec3,
+    bullets: Vec<SavedBullet>,
+    player_fire: f32,
+    enemy_fire: f32,
+    dive_timer: f32,
+    /// Seconds of a Time Attack run already played
+    time_limit: Option<f32>,
+    combo: Combo,
+    wave_stats: WaveStats,
+    director: Director,
+    stats: RunStats,
+    seed: u64,
+}
+
+impl SavedRun {
+    pub fn exists() -> bool {
+        data_path(SAVE_FILE).is_file()
+    }
+
+    /// Loads the saved run and deletes the file, so a run can only be resumed
+    /// once.
+    pub fn take() -> Option<Self> {
+        let path = data_path(SAVE_FILE);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                if err.kind() != io::ErrorKind::NotFound {
+                    warn!("Failed to read saved run: {err}");
+                }
+                return None;
+            }
+        };
+        if let Err(err) = fs::remove_file(&path) {
+            warn!("Failed to delete saved run: {err}");
+        }
+        Self::parse(&contents)
+    }
+
+    /// Malformed lines are skipped. Without any HP left there's nothing to
+    /// resume.
+    fn parse(contents: &str) -> Option<Self> {
+        let mut run = Self {
+            difficulty: Difficulty::default(),
+            mode: GameMode::default(),
+            stage: CampaignStage(None),
+            daily: DailyChallenge(None),
+            board: Board {
+                enemies: Vec::new(),
+                shields: Vec::new(),
+                front_enemies: HashMap::new(),
+                direction: Direction::Right,
+                march_progress: 0.,
+                score: 0,
+                hp: 0,
+                wave: 1,
+                boss_hp: None,
+            },
+            player: PLAYER_START,
+            bullets: Vec::new(),
+            player_fire: 0.,
+            enemy_fire: 0.,
+            dive_timer: 0.,
+            time_limit: None,
+            combo: Combo::default(),
+            wave_stats: WaveStats::default(),
+            director: Director::default(),
+            stats: RunStats::default(),
+            seed: 0,
+        };
+        for line in contents.lines() {
+            let mut fields = line.split_whitespace();
+            if let Some(key) = fields.next() {
+                run.parse_line(key, &fields.collect::<Vec<_>>());
+            }
+        }
+        (run.board.hp > 0).then_some(run)
+    }
+
+    fn parse_line(&mut self, key: &str, fields: &[&str]) -> Option<()> {
+        match (key, fields) {
+            ("difficulty", [name]) => {
+                self.difficulty = *Difficulty::ALL.iter().find(|d| d.name() == *name)?;
+            }
+            ("mode", [id]) => self.mode = *GameMode::ALL.iter().find(|m| m.id() == *id)?,
+            ("stage", [stage]) => self.stage = CampaignStage(Some(stage.parse().ok()?)),
+            ("daily", [day]) => {
+                self.daily = DailyChallenge(Some(Challenge::for_day(day.parse().ok()?)));
+            }
+            ("player", [x, y, z]) => self.player = vec3(x, y, z)?,
+            ("score", [score]) => self.board.score = score.parse().ok()?,
+            ("hp", [hp]) => self.board.hp = hp.parse().ok()?,
+            ("wave", [wave]) => self.board.wave = wave.parse().ok()?,
+            ("march_progress", [progress]) => {
+                self.board.march_progress = finite(progress)?.max(0.);
+            }
+            ("direction", [direction]) => {
+                self.board.direction = match *direction {
+                    "left" => Direction::Left,
+                    "right" => Direction::Right,
+                    _ => Direction::None,
+                };
+            }
+            ("boss", [hp]) => {
+                let hp: u32 = hp.parse().ok()?;
+                self.board.boss_hp = Some(hp.clamp(1, MAX_BOSS_HP));
+            }
+            ("enemy", [name, row, col, x, y, z, health]) => {
+                let enemy = *ENEMIES.iter().find(|e| e.name() == *name)?;
+                let position = Position {
+                    row: row.parse().ok()?,
+                    col: col.parse().ok()?,
+                };
+                // Without health it could never be killed
+                let health = health.parse::<u32>().ok()?.clamp(1, enemy.health());
+                self.board
+                    .enemies
+                    .push((vec3(x, y, z)?, position, enemy, health, None));
+            }
+            // Follows the line of the enemy on the dive
+            ("dive", [row, col, progress, fire, x, y, z, phase @ ..]) => {
+                let (row, col): (usize, usize) = (row.parse().ok()?, col.parse().ok()?);
+                let phase = match phase {
+                    ["attack", path @ ..] => {
+                        let path: Vec<_> = path
+                            .chunks(2)
+                            .map(|point| vec2(point.first()?, point.get(1)?))
+                            .collect::<Option<_>>()?;
+                        DivePhase::Attack {
+                            path: path.try_into().ok()?,
+                        }
+                    }
+                    ["exit"] => DivePhase::Exit,
+                    ["return", x, y] => DivePhase::Return { from: vec2(x, y)? },
+                    _ => return None,
+                };
+                let mut dive = Dive::new(vec3(x, y, z)?, phase);
+                dive.progress = finite(progress)?.clamp(0., 1.);
+                dive.fire_timer
+                    .set_elapsed(Duration::from_secs_f32(seconds(fire)?));
+                let (.., on_dive) = self
+                    .board
+                    .enemies
+                    .iter_mut()
+                    .find(|(_, position, ..)| position.row == row && position.col == col)?;
+                *on_dive = Some(dive);
+            }
+            ("front", [col, row]) => {
+                self.board
+                    .front_enemies
+                    .insert(col.parse().ok()?, row.parse().ok()?);
+            }
+            ("shield", [hits, x, y, z]) => {
+                let shield = Shield {
+                    hits: hits.parse().ok()?,
+                };
+                self.board.shields.push((vec3(x, y, z)?, shield));
+            }
+            ("bullet", [kind, x, y, z, vx, vy, width, height, heavy, homing, zig_zag]) => {
+                self.bullets.push(SavedBullet {
+                    bullet: match *kind {
+                        "player" => Bullet::Player,
+                        "enemy" => Bullet::Enemy,
+                        _ => return None,
+                    },
+                    translation: vec3(x, y, z)?,
+                    velocity: Vec2::new(vx.parse().ok()?, vy.parse().ok()?),
+                    size: Vec2::new(width.parse().ok()?, height.parse().ok()?),
+                    heavy: *heavy == "1",
+                    homing: *homing == "1",
+                    zig_zag: match *zig_zag {
+                        "-" => None,
+                        elapsed => Some(seconds(elapsed)?),
+                    },
+                });
+            }
+            ("player_fire", [elapsed]) => self.player_fire = seconds(elapsed)?,
+            ("enemy_fire", [elapsed]) => self.enemy_fire = seconds(elapsed)?,
+            ("dive_timer", [elapsed]) => self.dive_timer = seconds(elapsed)?,
+            ("time_limit", [elapsed]) => self.time_limit = Some(seconds(elapsed)?),
+            ("combo", [chain, elapsed]) => {
+                self.combo.chain = chain.parse().ok()?;
+                self.combo
+                    .window
+                    .set_elapsed(Duration::from_secs_f32(seconds(elapsed)?));
+            }
+            ("wave_stats", [shots_before, hits_before, damaged]) => {
+                self.wave_stats = WaveStats {
+                    shots_before: shots_before.parse().ok()?,
+                    hits_before: hits_before.parse().ok()?,
+                    damaged: *damaged == "1",
+                };
+            }
+            (
+                "director",
+                [
+                    shots_before,
+                    hits_before,
+                    hits_taken,
+                    elapsed,
+                    wave,
+                    wave_time,
+                    fire_scale,
+                    speed_scale,
+                ],
+            ) => {
+                let mut director = Director {
+                    shots_before: shots_before.parse().ok()?,
+                    hits_before: hits_before.parse().ok()?,
+                    hits_taken: hits_taken.parse().ok()?,
+                    wave: wave.parse().ok()?,
+                    wave_time: seconds(wave_time)?,
+                    fire_scale: finite(fire_scale)?.clamp(MIN_SCALE, MAX_SCALE),
+                    speed_scale: finite(speed_scale)?.clamp(MIN_SCALE, MAX_SCALE),
+                    ..default()
+                };
+                director
+                    .window
+                    .set_elapsed(Duration::from_secs_f32(seconds(elapsed)?));
+                self.director = director;
+            }
+            (
+                "stats",
+                [
+                    shots,
+                    hits,
+                    shields_lost,
+                    waves_cleared,
+                    time_survived,
+                    damage_taken,
+                ],
+            ) => {
+                self.stats = RunStats {
+                    shots: shots.parse().ok()?,
+                    hits: hits.parse().ok()?,
+                    shields_lost: shields_lost.parse().ok()?,
+                    waves_cleared: waves_cleared.parse().ok()?,
+                    time_survived: seconds(time_survived)?,
+                    damage_taken: damage_taken.parse().ok()?,
+                    kills: std::mem::take(&mut self.stats.kills),
+                    ..default()
+                };
+            }
+            ("kills", [name, count]) => {
+                let enemy = ENEMIES.iter().find(|e| e.name() == *name)?;
+                self.stats.kills.insert(enemy.name(), count.parse().ok()?);
+            }
+            ("seed", [seed]) => self.seed = seed.parse().ok()?,
+            _ => {}
+        }
+        Some(())
+    }
+
+    fn save(&self) {
+        if let Err(err) = write_data_file(SAVE_FILE, self.contents()) {
+            warn!("Failed to save run: {err}");
+        }
+    }
+
+    /// The save file `parse` reads back.
+    fn contents(&self) -> String {
+        let board = &self.board;
+        let mut contents = format!(
+            "difficulty {}\nplayer {}\nscore {}\nhp {}\nwave {}\ndirection {}\nmarch_progress {}\n",
+            self.difficulty.name(),
+            fmt_vec3(self.player),
+            board.score,
+            board.hp,
+            board.wave,
+            match board.direction {
+                Direction::Left => "left",
+                Direction::Right => "right",
+                Direction::None => "none",
+            },
+            board.march_progress,
+        );
+        contents += &format!("mode {}\n", self.mode.id());
+        if let Some(stage) = self.stage.0 {
+            contents += &format!("stage {stage}\n");
+        }
+        if let Some(challenge) = self.daily.0 {
+            contents += &format!("daily {}\n", challenge.day);
+        }
+        if let Some(hp) = board.boss_hp {
+            contents += &format!("boss {hp}\n");
+        }
+        for (translation, position, enemy, health, _) in &board.enemies {
+            contents += &format!(
+                "enemy {} {} {} {} {health}\n",
+                enemy.name(),
+                position.row,
+                position.col,
+                fmt_vec3(*translation)
+            );
+        }
+        for (_, position, _, _, dive) in &board.enemies {
+            let Some(dive) = dive else {
+                continue;
+            };
+            let phase = match dive.phase {
+                DivePhase::Attack { path } => {
+                    path.iter().fold("attack".to_string(), |phase, point| {
+                        format!("{phase} {} {}", point.x, point.y)
+                    })
+                }
+                DivePhase::Exit => "exit".to_string(),
+                DivePhase::Return { from } => format!("return {} {}", from.x, from.y),
+            };
+            contents += &format!(
+                "dive {} {} {} {} {} {phase}\n",
+                position.row,
+                position.col,
+                dive.progress,
+                dive.fire_timer.elapsed_secs(),
+                fmt_vec3(dive.slot)
+            );
+        }
+        for (col, row) in &board.front_enemies {
+            contents += &format!("front {col} {row}\n");
+        }
+        for (translation, shield) in &board.shields {
+            contents += &format!("shield {} {}\n", shield.hits, fmt_vec3(*translation));
+        }
+        for bullet in &self.bullets {
+            contents += &format!(
+                "bullet {} {} {} {} {} {} {} {} {}\n",
+                match bullet.bullet {
+                    Bullet::Player => "player",
+                    Bullet::Enemy => "enemy",
+                },
+                fmt_vec3(bullet.translation),
+                bullet.velocity.x,
+                bullet.velocity.y,
+                bullet.size.x,
+                bullet.size.y,
+                u8::from(bullet.heavy),
+                u8::from(bullet.homing),
+                bullet
+                    .zig_zag
+                    .map_or("-".to_string(), |elapsed| elapsed.to_string())
+            );
+        }
+        if let Some(elapsed) = self.time_limit {
+            contents += &format!("time_limit {elapsed}\n");
+        }
+        contents += &format!(
+            "player_fire {}\nenemy_fire {}\ndive_timer {}\nseed {}\n",
+            self.player_fire, self.enemy_fire, self.dive_timer, self.seed
+        );
+
+        let combo = &self.combo;
+        let stats = &self.wave_stats;
+        contents += &format!(
+            "combo {} {}\nwave_stats {} {} {}\n",
+            combo.chain,
+            combo.window.elapsed_secs(),
+            stats.shots_before,
+            stats.hits_before,
+            u8::from(stats.damaged)
+        );
+        let director = &self.director;
+        contents += &format!(
+            "director {} {} {} {} {} {} {} {}\n",
+            director.shots_before,
+            director.hits_before,
+            director.hits_taken,
+            director.window.elapsed_secs(),
+            director.wave,
+            director.wave_time,
+            director.fire_scale,
+            director.speed_scale
+        );
+        let stats = &self.stats;
+        contents += &format!(
+            "stats {} {} {} {} {} {}\n",
+            stats.shots,
+            stats.hits,
+            stats.shields_lost,
+            stats.waves_cleared,
+            stats.time_survived,
+            stats.damage_taken
+        );
+        for (name, count) in &stats.kills {
+            contents += &format!("kills {name} {count}\n");
+        }
+        contents
+    }
+
+    /// Spawns the saved player, board and bullets and inserts the timers and
+    /// RNG the way they were.
+    pub fn restore(&self, commands: &mut Commands, level: &Level) {
+        spawn_player(commands, self.player);
+        self.board.clone().spawn(commands, level);
+
+        for saved in &self.bullets {
+            let bullet = match saved.bullet {
+                Bullet::Player => spawn_player_bullet(commands, saved.translation),
+                Bullet::Enemy => spawn_enemy_bullet(commands, saved.translation, saved.velocity),
+            };
+            commands.entity(bullet).insert((
+                Transform {
+                    translation: saved.translation,
+                    scale: saved.size.extend(1.),
+                    ..default()
+                },
+                Collider(Aabb2d::new(saved.translation.truncate(), saved.size / 2.)),
+                Velocity(saved.velocity),
+            ));
+            if saved.heavy {
+                commands.entity(bullet).insert(Heavy);
+            }
+            if saved.homing {
+                commands.entity(bullet).insert(Homing);
+            }
+            if let Some(elapsed) = saved.zig_zag {
+                commands.entity(bullet).insert(ZigZag::new(elapsed));
+            }
+        }
+
+        let mut player_fire = Timer::from_seconds(1.0 / PLAYER_FIRE_RATE, TimerMode::Repeating);
+        player_fire.set_elapsed(Duration::from_secs_f32(self.player_fire));
+        commands.insert_resource(PlayerFireTimer(player_fire));
+        let mut enemy_fire = Timer::from_seconds(1.0 / ENEMY_FIRE_RATE, TimerMode::Repeating);
+        enemy_fire.set_elapsed(Duration::from_secs_f32(self.enemy_fire));
+        commands.insert_resource(EnemyFireTimer(enemy_fire));
+        let mut dive_timer = DiveTimer::default();
+        dive_timer
+            .0
+            .set_elapsed(Duration::from_secs_f32(self.dive_timer));
+        commands.insert_resource(dive_timer);
+
+        if let Some(elapsed) = self.time_limit {
+            commands.insert_resource(TimeLimit::new(elapsed));
+        }
+
+        commands.insert_resource(self.combo.clone());
+        commands.insert_resource(self.wave_stats.clone());
+        commands.insert_resource(self.director.clone());
+        commands.insert_resource(RunStats {
+            score: self.board.score,
+            difficulty: self.difficulty,
+            mode: self.mode,
+            ..self.stats.clone()
+        });
+
+        commands.insert_resource(MyRng(StdRng::seed_from_u64(self.seed)));
+    }
+}
+
+fn vec2(x: &str, y: &str) -> Option<Vec2> {
+    Some(Vec2::new(x.parse().ok()?, y.parse().ok()?))
+}
+
+fn vec3(x: &str, y: &str, z: &str) -> Option<Vec3> {
+    Some(Vec3::new(x.parse().ok()?, y.parse().ok()?, z.parse().ok()?))
+}
+
+fn finite(value: &str) -> Option<f32> {
+    value.parse().ok().filter(|value: &f32| value.is_finite())
+}
+
+/// Time a timer has run for, which has to fit in a `Duration`.
+fn seconds(value: &str) -> Option<f32> {
+    let seconds = value.parse().ok()?;
+    Duration::try_from_secs_f32(seconds).ok()?;
+    Some(seconds)
+}
+
+fn fmt_vec3(v: Vec3) -> String {
+    format!("{} {} {}", v.x, v.y, v.z)
+}
+
+/// How far the run has got besides the board, all of which a resumed run
+/// picks up again.
+#[derive(SystemParam)]
+struct Progress<'w> {
+    player_fire: Res<'w, PlayerFireTimer>,
+    enemy_fire: Res<'w, EnemyFireTimer>,
+    dive_timer: Res<'w, DiveTimer>,
+    time_limit: Option<Res<'w, TimeLimit>>,
+    combo: Res<'w, Combo>,
+    wave_stats: Res<'w, WaveStats>,
+    director: Res<'w, Director>,
+    stats: Res<'w, RunStats>,
+}
+
+/// Saves a single-player run when the game is closed, or when Escape is
+/// pressed, which also goes back to the menu.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn save_run(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut exit_reader: EventReader<AppExit>,
+    players: Res<Players>,
+    difficulty: Res<Difficulty>,
+    mode: Res<GameMode>,
+    stage: Res<CampaignStage>,
+    daily: Res<DailyChallenge>,
+    current_board: CurrentBoard,
+    player_query: Query<&Transform, With<Player>>,
+    bullet_query: Query<(
+        &Bullet,
+        &Transform,
+        &Velocity,
+        Has<Heavy>,
+        Has<Homing>,
+        Option<&ZigZag>,
+    )>,
+    progress: Progress,
+    mut rng: ResMut<MyRng>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    let quit = exit_reader.read().next().is_some();
+    let suspend = keyboard_input.just_pressed(KeyCode::Escape);
+    if !(quit || suspend) || *players != Players::One {
+        return;
+    }
+
+    let board = current_board.capture();
+    if board.hp == 0 {
+        return;
+    }
+
+    // Reseed, so the resumed run rolls exactly what this one would have
+    let seed = rng.0.random();
+    rng.0 = StdRng::seed_from_u64(seed);
+
+    SavedRun {
+        difficulty: *difficulty,
+        mode: *mode,
+        stage: *stage,
+        daily: *daily,
+        board,
+        // A player waiting to respawn comes back at the start
+        player: player_query
+            .single()
+            .map_or(PLAYER_START, |transform| transform.translation),
+        bullets: bullet_query
+            .iter()
+            .map(
+                |(&bullet, transform, velocity, heavy, homing, zig_zag)| SavedBullet {
+                    bullet,
+                    translation: transform.translation,
+                    velocity: velocity.0,
+                    size: transform.scale.truncate(),
+                    heavy,
+                    homing,
+                    zig_zag: zig_zag.map(|zig_zag| zig_zag.0.elapsed_secs()),
+                },
+            )
+            .collect(),
+        player_fire: progress.player_fire.0.elapsed_secs(),
+        enemy_fire: progress.enemy_fire.0.elapsed_secs(),
+        dive_timer: progress.dive_timer.0.elapsed_secs(),
+        time_limit: progress
+            .time_limit
+            .map(|time_limit| time_limit.0.elapsed_secs()),
+        combo: progress.combo.clone(),
+        wave_stats: progress.wave_stats.clone(),
+        director: progress.director.clone(),
+        stats: progress.stats.clone(),
+        seed,
+    }
+    .save();
+
+    if suspend {
+        game_state.set(GameState::Menu);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut run = SavedRun::parse("hp 2\nscore 1200\nwave 4\nseed 99\n").unwrap();
+        run.difficulty = Difficulty::Hard;
+        run.mode = GameMode::TimeAttack;
+        run.time_limit = Some(30.5);
+        let mut dive = Dive::new(
+            Vec3::new(-50., 200., 0.),
+            DivePhase::Attack {
+                path: [
+                    Vec2::new(1., 2.),
+                    Vec2::new(3., 4.),
+                    Vec2::new(5., 6.),
+                    Vec2::new(7., 8.),
+                ],
+            },
+        );
+        dive.progress = 0.25;
+        run.board.enemies = vec![
+            (
+                Vec3::new(10., 150., 0.),
+                Position { row: 0, col: 1 },
+                Enemy::Normal,
+                1,
+                None,
+            ),
+            (
+                Vec3::new(-20., 40., 0.),
+                Position { row: 2, col: 0 },
+                Enemy::Lancer,
+                2,
+                Some(dive),
+            ),
+        ];
+        run.bullets = vec![SavedBullet {
+            bullet: Bullet::Enemy,
+            translation: Vec3::new(0., 50., 0.),
+            velocity: Vec2::new(200., -150.),
+            size: Vec2::splat(5.),
+            heavy: false,
+            homing: false,
+            zig_zag: Some(0.125),
+        }];
+        run.board.march_progress = 7.5;
+        run.combo.chain = 6;
+        run.director.hits_before = 3;
+        run.director.fire_scale = 1.25;
+        run.stats.shots = 40;
+        run.stats.kills.insert(Enemy::Sniper.name(), 5);
+
+        let parsed = SavedRun::parse(&run.contents()).unwrap();
+        assert_eq!(parsed.difficulty, run.difficulty);
+        assert_eq!(parsed.mode, run.mode);
+        assert_eq!(parsed.board.score, 1200);
+        assert_eq!(parsed.board.wave, 4);
+        assert_eq!(parsed.board.march_progress, 7.5);
+        assert_eq!(parsed.seed, 99);
+        assert_eq!(parsed.time_limit, Some(30.5));
+
+        let healths: Vec<_> = parsed.board.enemies.iter().map(|e| e.3).collect();
+        assert_eq!(healths, [1, 2]);
+        let dive = parsed.board.enemies[1].4.as_ref().unwrap();
+        assert_eq!(dive.slot, Vec3::new(-50., 200., 0.));
+        assert_eq!(dive.progress, 0.25);
+        assert!(matches!(
+            dive.phase,
+            DivePhase::Attack { path } if path[3] == Vec2::new(7., 8.)
+        ));
+
+        assert_eq!(parsed.bullets[0].zig_zag, Some(0.125));
+        assert!(!parsed.bullets[0].homing);
+        assert_eq!(parsed.combo.chain, 6);
+        assert_eq!(parsed.director.hits_before, 3);
+        assert_eq!(parsed.director.fire_scale, 1.25);
+        assert_eq!(parsed.stats.shots, 40);
+        assert_eq!(parsed.stats.kills, run.stats.kills);
+    }
+
+    #[test]
+    fn nothing_to_resume_without_hp() {
+        assert!(SavedRun::parse("score 100\nwave 2\n").is_none());
+        assert!(SavedRun::parse("hp 0\n").is_none());
+        assert!(SavedRun::parse("").is_none());
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let run = SavedRun::parse(
+            "hp 3\nenemy Normal 0 0 1 2\nenemy Nobody 0 0 1 2 3 1\ndive 5 5 0 0 0 0 0 exit\n\
+             director 1 2 3\nmode sideways\nbullet laser 0 0 0 0 0 5 5 0 0 -\n",
+        )
+        .unwrap();
+        assert!(run.board.enemies.is_empty());
+        assert!(run.bullets.is_empty());
+        assert_eq!(run.mode, GameMode::default());
+        assert_eq!(run.director.hits_before, 0);
+    }
+
+    #[test]
+    fn health_is_clamped() {
+        let run =
+            SavedRun::parse("hp 3\nboss 0\nenemy Lancer 0 0 1 2 3 0\nenemy Normal 0 1 1 2 3 9\n")
+                .unwrap();
+        let healths: Vec<_> = run.board.enemies.iter().map(|e| e.3).collect();
+        assert_eq!(healths, [1, 1]);
+        assert_eq!(run.board.boss_hp, Some(1));
+    }
+
+    #[test]
+    fn bad_timers_are_skipped() {
+        let run =
+            SavedRun::parse("hp 3\nplayer_fire -1\nenemy_fire NaN\ntime_limit 1e30\n").unwrap();
+        assert_eq!(run.player_fire, 0.);
+        assert_eq!(run.enemy_fire, 0.);
+        assert_eq!(run.time_limit, None);
+    }
+}
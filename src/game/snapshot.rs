@@ -0,0 +1,202 @@
+//! In-memory save states, captured through reflection as a `DynamicScene` of
+//! everything on the game screen along with the board's resources.
+
+use crate::GameState;
+use crate::Players;
+
+use super::Combo;
+use super::Director;
+use super::EnemyDirection;
+use super::EnemyFireTimer;
+use super::FrontEnemies;
+use super::HotSeat;
+use super::Hp;
+use super::MarchProgress;
+use super::MarchStyle;
+use super::MyRng;
+use super::OnGameScreen;
+use super::PlayerFireTimer;
+use super::RunStats;
+use super::Score;
+use super::Wave;
+use super::combo::WaveStats;
+use super::dive::DiveTimer;
+use super::lives::Respawn;
+use super::mode::{Reinforcements, TimeLimit};
+
+use bevy::ecs::entity::EntityHashMap;
+use bevy::input::common_conditions::input_just_pressed;
+use bevy::prelude::*;
+
+pub fn snapshot_plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            quick_save.run_if(input_just_pressed(KeyCode::F5)),
+            quick_load.run_if(input_just_pressed(KeyCode::F9).and(resource_exists::<Snapshot>)),
+        )
+            // Rewinding one side would desync a versus match
+            .run_if(in_state(GameState::Running).and(not(resource_equals(Players::Versus)))),
+    )
+    .add_systems(OnExit(GameState::Running), remove_snapshot);
+}
+
+/// The game screen frozen at one moment.
+///
+/// Resources that can't be reflected, like the RNG, are cloned alongside the
+/// scene.
+#[derive(Resource)]
+pub struct Snapshot {
+    scene: DynamicScene,
+    rng: MyRng,
+    stats: RunStats,
+    hot_seat: Option<HotSeat>,
+}
+
+impl Snapshot {
+    /// Captures every `OnGameScreen` entity with its descendants.
+    pub fn capture(world: &mut World) -> Self {
+        let mut roots = world.query_filtered::<Entity, With<OnGameScreen>>();
+        let mut children = world.query::<&Children>();
+        let children = children.query(world);
+        let entities: Vec<Entity> = roots
+            .iter(world)
+            .flat_map(|root| std::iter::once(root).chain(children.iter_descendants(root)))
+            .collect();
+
+        let scene = DynamicSceneBuilder::from_world(world)
+            .deny_all_resources()
+            .allow_resource::<Score>()
+            .allow_resource::<Hp>()
+            .allow_resource::<Wave>()
+            .allow_resource::<FrontEnemies>()
+            .allow_resource::<EnemyDirection>()
+            .allow_resource::<MarchStyle>()
+            .allow_resource::<MarchProgress>()
+            .allow_resource::<PlayerFireTimer>()
+            .allow_resource::<EnemyFireTimer>()
+            .allow_resource::<Combo>()
+            .allow_resource::<TimeLimit>()
+            .allow_resource::<Reinforcements>()
+            .allow_resource::<WaveStats>()
+            .allow_resource::<Director>()
+            .allow_resource::<DiveTimer>()
+            .allow_resource::<Respawn>()
+            .extract_entities(entities.into_iter())
+            .extract_resources()
+            .build();
+        Self {
+            scene,
+            rng: world.resource::<MyRng>().clone(),
+            stats: world.resource::<RunStats>().clone(),
+            hot_seat: world.get_resource::<HotSeat>().cloned(),
+        }
+    }
+
+    /// Replaces the game screen with the snapshot's entities, which are
+    /// spawned anew, and puts the resources back the way they were.
+    pub fn restore(&self, world: &mut World) {
+        let mut roots = world.query_filtered::<Entity, With<OnGameScreen>>();
+        for entity in roots.iter(world).collect::<Vec<_>>() {
+            // Children may have gone already with their parent
+            if let Ok(entity) = world.get_entity_mut(entity) {
+                entity.despawn();
+            }
+        }
+
+        // Only there some of the time, so the scene won't replace them
+        world.remove_resource::<Respawn>();
+        world.remove_resource::<HotSeat>();
+
+        if let Err(err) = self
+            .scene
+            .write_to_world(world, &mut EntityHashMap::default())
+        {
+            error!("Failed to restore snapshot: {err}");
+        }
+
+        world.insert_resource(self.rng.clone());
+        world.insert_resource(self.stats.clone());
+        if let Some(hot_seat) = &self.hot_seat {
+            world.insert_resource(hot_seat.clone());
+        }
+    }
+}
+
+fn quick_save(world: &mut World) {
+    let snapshot = Snapshot::capture(world);
+    world.insert_resource(snapshot);
+}
+
+fn quick_load(world: &mut World) {
+    world.resource_scope(|world, snapshot: Mut<Snapshot>| snapshot.restore(world));
+}
+
+fn remove_snapshot(mut commands: Commands) {
+    commands.remove_resource::<Snapshot>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Level;
+
+    use rand::prelude::*;
+
+    fn world() -> World {
+        let registry = AppTypeRegistry::default();
+        {
+            let mut registry = registry.write();
+            registry.register::<OnGameScreen>();
+            registry.register::<Transform>();
+            registry.register::<Score>();
+            registry.register::<Respawn>();
+        }
+        let mut world = World::new();
+        world.insert_resource(registry);
+        world
+    }
+
+    fn translations(world: &mut World) -> Vec<Vec3> {
+        let mut query = world.query_filtered::<&Transform, With<OnGameScreen>>();
+        query
+            .iter(world)
+            .map(|transform| transform.translation)
+            .collect()
+    }
+
+    #[test]
+    fn restore_puts_the_world_back() {
+        let mut world = world();
+        world.spawn((Transform::from_xyz(1., 2., 0.), OnGameScreen));
+        world.insert_resource(Score(10));
+        world.insert_resource(MyRng(StdRng::seed_from_u64(7)));
+        world.insert_resource(RunStats {
+            shots: 3,
+            ..default()
+        });
+        world.insert_resource(HotSeat::new(3, &Level::default()));
+
+        let snapshot = Snapshot::capture(&mut world);
+
+        // Play on: the player dies and the other one takes over
+        world.spawn((Transform::from_xyz(5., 5., 0.), OnGameScreen));
+        world.resource_mut::<Score>().0 = 50;
+        world.resource_mut::<MyRng>().0.random::<u64>();
+        world.resource_mut::<RunStats>().shots = 9;
+        world.resource_mut::<HotSeat>().current = 2;
+        world.insert_resource(Respawn(Timer::from_seconds(2., TimerMode::Once)));
+
+        snapshot.restore(&mut world);
+
+        assert_eq!(translations(&mut world), [Vec3::new(1., 2., 0.)]);
+        assert_eq!(world.resource::<Score>().0, 10);
+        assert_eq!(
+            world.resource_mut::<MyRng>().0.random::<u64>(),
+            StdRng::seed_from_u64(7).random::<u64>()
+        );
+        assert_eq!(world.resource::<RunStats>().shots, 3);
+        assert_eq!(world.resource::<HotSeat>().current, 1);
+        assert!(!world.contains_resource::<Respawn>());
+    }
+}
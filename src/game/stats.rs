@@ -0,0 +1,111 @@
+use crate::Difficulty;
+use crate::GameMode;
+use crate::GameState;
+
+use super::Bullet;
+use super::Collision;
+use super::EnemyKilled;
+use super::Layer;
+use super::PlayerHit;
+use super::Score;
+use super::WaveCleared;
+use super::versus::lockstep_ready;
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+pub fn stats_plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameState::Running), reset_stats)
+        .add_systems(
+            FixedUpdate,
+            collect_stats.run_if(in_state(GameState::Running).and(lockstep_ready)),
+        );
+}
+
+/// Everything that happened over one run, for the game-over summary.
+#[derive(Resource, Default, Clone)]
+pub struct RunStats {
+    pub score: u32,
+    pub difficulty: Difficulty,
+    pub mode: GameMode,
+    pub shots: u32,
+    pub hits: u32,
+    pub kills: BTreeMap<&'static str, u32>,
+    pub shields_lost: u32,
+    pub waves_cleared: u32,
+    pub time_survived: f32,
+    pub damage_taken: u32,
+    pub outcome: Outcome,
+}
+
+/// How a run ended.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    #[default]
+    OutOfLives,
+    /// The clock ran out on a Time Attack run with lives to spare
+    TimeUp,
+    /// The campaign stage's boss was beaten
+    StageClear,
+}
+
+impl Outcome {
+    pub fn title(self) -> &'static str {
+        match self {
+            Outcome::OutOfLives => "GAME OVER",
+            Outcome::TimeUp => "TIME UP",
+            Outcome::StageClear => "STAGE CLEAR",
+        }
+    }
+}
+
+impl RunStats {
+    pub fn accuracy(&self) -> f32 {
+        if self.shots == 0 {
+            0.
+        } else {
+            (self.hits as f32 / self.shots as f32).min(1.)
+        }
+    }
+}
+
+pub fn reset_stats(mut commands: Commands, difficulty: Res<Difficulty>, mode: Res<GameMode>) {
+    commands.insert_resource(RunStats {
+        difficulty: *difficulty,
+        mode: *mode,
+        ..default()
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn collect_stats(
+    mut stats: ResMut<RunStats>,
+    bullet_query: Query<&Bullet, Added<Bullet>>,
+    mut collision_reader: EventReader<Collision>,
+    mut kill_reader: EventReader<EnemyKilled>,
+    mut hit_reader: EventReader<PlayerHit>,
+    mut wave_reader: EventReader<WaveCleared>,
+    score: Res<Score>,
+    time: Res<Time<Fixed>>,
+) {
+    stats.score = score.0;
+    stats.time_survived += time.delta_secs();
+    stats.shots += bullet_query
+        .iter()
+        .filter(|bullet| matches!(bullet, Bullet::Player))
+        .count() as u32;
+
+    // Only player bullets hit enemies and their bullets
+    stats.hits += collision_reader
+        .read()
+        .filter(|collision| matches!(collision.layer, Layer::Enemy | Layer::EnemyBullet))
+        .count() as u32;
+
+    for EnemyKilled { enemy, .. } in kill_reader.read() {
+        *stats.kills.entry(enemy.name()).or_default() += 1;
+    }
+
+    stats.damage_taken += hit_reader.read().count() as u32;
+    stats.waves_cleared += wave_reader.read().count() as u32;
+}
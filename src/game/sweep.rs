@@ -0,0 +1,90 @@
+use bevy::math::Vec2;
+use bevy::math::bounding::{Aabb2d, BoundingVolume};
+
+/// Time of impact, between 0 and 1, of `aabb` moving by `displacement` into
+/// `target`, or `None` if it never touches it along the way.
+pub fn sweep(aabb: &Aabb2d, displacement: Vec2, target: &Aabb2d) -> Option<f32> {
+    // Shrink the moving box to a point by growing the target by its size
+    let half_size = aabb.half_size();
+    let min = target.min - half_size;
+    let max = target.max + half_size;
+    let origin = aabb.center();
+
+    let mut enter = 0.0_f32;
+    let mut exit = 1.0_f32;
+    for axis in 0..2 {
+        if displacement[axis] == 0. {
+            if !(min[axis]..=max[axis]).contains(&origin[axis]) {
+                return None;
+            }
+            continue;
+        }
+
+        let t0 = (min[axis] - origin[axis]) / displacement[axis];
+        let t1 = (max[axis] - origin[axis]) / displacement[axis];
+        enter = enter.max(t0.min(t1));
+        exit = exit.min(t0.max(t1));
+        if enter > exit {
+            return None;
+        }
+    }
+
+    Some(enter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f32, y: f32) -> Aabb2d {
+        Aabb2d::new(Vec2::new(x, y), Vec2::ONE)
+    }
+
+    #[test]
+    fn hits_a_box_in_the_way() {
+        let toi = sweep(&square(0., 0.), Vec2::new(10., 0.), &square(5., 0.));
+        assert_eq!(toi, Some(0.3));
+
+        // Same box, coming from the other side
+        let toi = sweep(&square(10., 0.), Vec2::new(-10., 0.), &square(5., 0.));
+        assert_eq!(toi, Some(0.3));
+    }
+
+    #[test]
+    fn overlapping_boxes_hit_straight_away() {
+        assert_eq!(
+            sweep(&square(0., 0.), Vec2::new(0., 5.), &square(1., 1.)),
+            Some(0.)
+        );
+        assert_eq!(
+            sweep(&square(0., 0.), Vec2::ZERO, &square(1., 1.)),
+            Some(0.)
+        );
+    }
+
+    #[test]
+    fn misses_boxes_off_the_path() {
+        // Passes above
+        assert_eq!(
+            sweep(&square(0., 0.), Vec2::new(10., 0.), &square(5., 3.)),
+            None
+        );
+        // Stops short
+        assert_eq!(
+            sweep(&square(0., 0.), Vec2::new(2., 0.), &square(5., 0.)),
+            None
+        );
+        // Moves away
+        assert_eq!(
+            sweep(&square(0., 0.), Vec2::new(-10., 0.), &square(5., 0.)),
+            None
+        );
+        // Doesn't move
+        assert_eq!(sweep(&square(0., 0.), Vec2::ZERO, &square(5., 0.)), None);
+        // Diagonal that clears the corner
+        assert_eq!(
+            sweep(&square(0., 0.), Vec2::new(10., 10.), &square(6., 0.)),
+            None
+        );
+    }
+}
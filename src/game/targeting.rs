@@ -0,0 +1,64 @@
+use super::ENEMY_FIRE_RATE;
+use super::board::ENEMY_SPACING;
+
+use bevy::prelude::*;
+
+use rand::prelude::*;
+
+/// How the formation picks who shoots, and how often.
+#[derive(Resource, Clone, Copy)]
+pub struct TargetingPolicy {
+    /// How strongly shooters near the player's column are preferred, where
+    /// 0 picks uniformly from the front row
+    pub player_bias: f32,
+    /// How many front-row enemies fire together in one volley
+    pub shooters: usize,
+    /// Volleys per second at the start of a game
+    pub fire_rate: f32,
+    /// Extra volleys per second added every wave
+    pub aggression: f32,
+    /// No new volleys while this many enemy bullets are on screen
+    pub bullet_cap: usize,
+}
+
+impl Default for TargetingPolicy {
+    fn default() -> Self {
+        Self {
+            player_bias: 3.,
+            shooters: 1,
+            fire_rate: ENEMY_FIRE_RATE,
+            aggression: 0.5,
+            bullet_cap: 40,
+        }
+    }
+}
+
+impl TargetingPolicy {
+    pub fn fire_rate(&self, wave: u32) -> f32 {
+        self.fire_rate + self.aggression * wave.saturating_sub(1) as f32
+    }
+
+    /// Picks this volley's shooters out of `candidates`, favouring the ones
+    /// closest to `player_x`.
+    pub fn choose_shooters<T: Copy>(
+        &self,
+        candidates: &[(Vec3, T)],
+        player_x: Option<f32>,
+        bullets: usize,
+        rng: &mut impl Rng,
+    ) -> Vec<(Vec3, T)> {
+        let amount = self.shooters.min(self.bullet_cap.saturating_sub(bullets));
+        let weight = |&(translation, _): &(Vec3, T)| match player_x {
+            Some(player_x) => {
+                let columns = (translation.x - player_x).abs() / ENEMY_SPACING;
+                1. + self.player_bias / (1. + columns * columns)
+            }
+            None => 1.,
+        };
+
+        candidates
+            .choose_multiple_weighted(rng, amount, weight)
+            .map(|shooters| shooters.copied().collect())
+            .unwrap_or_default()
+    }
+}
@@ -1,35 +1,83 @@
+use crate::Difficulty;
 use crate::GameAssets;
+use crate::GameMode;
 use crate::GameState;
 
+use super::Boss;
+use super::Combo;
+use super::Health;
+use super::HotSeat;
 use super::Hp;
+use super::MAX_HP;
 use super::OnGameScreen;
-use super::STARTING_HP;
+use super::RunStats;
 use super::Score;
+use super::VersusState;
+use super::mode::TimeLimit;
 
 use bevy::prelude::*;
 
 pub fn ui_plugin(app: &mut App) {
-    app.add_systems(OnEnter(GameState::Running), setup_ui)
+    app.register_type::<Heart>()
+        .register_type::<ScoreText>()
+        .register_type::<ComboText>()
+        .register_type::<ModeText>()
+        .register_type::<PlayerText>()
+        .register_type::<RivalText>()
+        .register_type::<BossBar>()
+        .register_type::<BossBarFill>()
+        .add_systems(OnEnter(GameState::Running), setup_ui)
         .add_systems(
             Update,
             (
                 // UI
                 update_score_text,
+                update_combo_text,
+                update_mode_text,
                 update_hearts,
+                update_player_text,
+                update_rival_text,
+                update_boss_bar,
             )
                 .run_if(in_state(GameState::Running)),
         );
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 struct Heart {
     number: u8,
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 struct ScoreText;
 
-fn setup_ui(mut commands: Commands, assets: Res<GameAssets>) {
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct ComboText;
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct ModeText;
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct PlayerText;
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct RivalText;
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct BossBar;
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct BossBarFill;
+
+fn setup_ui(mut commands: Commands, assets: Res<GameAssets>, difficulty: Res<Difficulty>) {
     commands
         .spawn((
             Node {
@@ -56,6 +104,61 @@ fn setup_ui(mut commands: Commands, assets: Res<GameAssets>) {
                 ScoreText,
             ));
 
+            // Combo multiplier and the time left to keep it going
+            parent.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 20.0,
+                    font: assets.font_press_start.clone(),
+                    ..default()
+                },
+                TextColor(Color::srgb(1., 0.8, 0.2)),
+                ComboText,
+            ));
+
+            // Difficulty, fixed for the whole game
+            parent.spawn((
+                Text::new(difficulty.name()),
+                TextFont {
+                    font_size: 20.0,
+                    font: assets.font_press_start.clone(),
+                    ..default()
+                },
+            ));
+
+            // Time Attack countdown or Survival clock
+            parent.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 20.0,
+                    font: assets.font_press_start.clone(),
+                    ..default()
+                },
+                ModeText,
+            ));
+
+            // Current player in hot-seat games
+            parent.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 20.0,
+                    font: assets.font_press_start.clone(),
+                    ..default()
+                },
+                PlayerText,
+            ));
+
+            // Rival's progress in versus games
+            parent.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 20.0,
+                    font: assets.font_press_start.clone(),
+                    ..default()
+                },
+                RivalText,
+            ));
+
             // HP Visualisation
             parent
                 .spawn(Node {
@@ -65,7 +168,7 @@ fn setup_ui(mut commands: Commands, assets: Res<GameAssets>) {
                     ..default()
                 })
                 .with_children(|parent| {
-                    for x in 1..=STARTING_HP {
+                    for x in 1..=MAX_HP {
                         parent.spawn((
                             Node {
                                 margin: UiRect::all(Val::Px(4.0)),
@@ -79,6 +182,38 @@ fn setup_ui(mut commands: Commands, assets: Res<GameAssets>) {
                     }
                 });
         });
+
+    // Boss health, hidden until a boss shows up
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            top: Val::Px(20.0),
+            justify_content: JustifyContent::Center,
+            display: Display::None,
+            ..default()
+        },
+        BossBar,
+        OnGameScreen,
+        children![(
+            Node {
+                width: Val::Px(400.0),
+                height: Val::Px(16.0),
+                border: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            BorderColor(Color::WHITE),
+            children![(
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(1., 0.1, 0.1)),
+                BossBarFill,
+            )],
+        )],
+    ));
 }
 
 fn update_hearts(hp: Res<Hp>, mut query: Query<(&mut ImageNode, &Heart)>) {
@@ -97,3 +232,97 @@ fn update_score_text(score: Res<Score>, mut query: Query<&mut Text, With<ScoreTe
         **text = format!("Score: {value}");
     }
 }
+
+fn update_combo_text(combo: Res<Combo>, mut query: Query<&mut Text, With<ComboText>>) {
+    if let Ok(mut text) = query.single_mut() {
+        let multiplier = combo.multiplier();
+        **text = if multiplier > 1 {
+            let remaining = combo.window.remaining_secs();
+            format!("Combo x{multiplier} {remaining:.1}s")
+        } else {
+            String::new()
+        };
+    }
+}
+
+fn update_mode_text(
+    mode: Res<GameMode>,
+    time_limit: Option<Res<TimeLimit>>,
+    stats: Res<RunStats>,
+    mut query: Query<&mut Text, With<ModeText>>,
+) {
+    if let Ok(mut text) = query.single_mut() {
+        let clock = |secs: f32| format!("{}:{:02}", (secs / 60.) as u32, secs as u32 % 60);
+        **text = match (*mode, time_limit) {
+            (GameMode::TimeAttack, Some(time_limit)) => {
+                // Rounded up, so it reads 0:00 just as the run ends
+                format!("Time {}", clock(time_limit.0.remaining_secs().ceil()))
+            }
+            (GameMode::Survival, _) => format!("Survived {}", clock(stats.time_survived)),
+            (GameMode::OneLife, _) => "One-Life x2".to_string(),
+            _ => String::new(),
+        };
+    }
+}
+
+fn update_player_text(
+    hot_seat: Option<Res<HotSeat>>,
+    mut query: Query<&mut Text, With<PlayerText>>,
+) {
+    if let Ok(mut text) = query.single_mut() {
+        **text = match hot_seat {
+            Some(hot_seat) => format!("Player {}", hot_seat.current),
+            None => String::new(),
+        };
+    }
+}
+
+fn update_rival_text(
+    versus: Option<Res<VersusState>>,
+    mut query: Query<&mut Text, With<RivalText>>,
+) {
+    if let Ok(mut text) = query.single_mut() {
+        **text = match versus.as_ref().map(|versus| versus.rival) {
+            Some(Some(rival)) => {
+                let input = versus.as_ref().map(|versus| versus.rival_input);
+                let moving = match input.map_or(0, |input| input.direction) {
+                    ..0 => "<",
+                    0 => "-",
+                    _ => ">",
+                };
+                let firing = if input.is_some_and(|input| input.fire) {
+                    "*"
+                } else {
+                    " "
+                };
+                format!(
+                    "Rival: {} HP:{} Left:{} {moving}{firing}",
+                    rival.score, rival.hp, rival.enemies
+                )
+            }
+            Some(None) => "Waiting for rival...".to_string(),
+            None => String::new(),
+        };
+    }
+}
+
+fn update_boss_bar(
+    boss_query: Query<(&Boss, &Health)>,
+    mut bar_query: Query<&mut Node, (With<BossBar>, Without<BossBarFill>)>,
+    mut fill_query: Query<&mut Node, With<BossBarFill>>,
+) {
+    let boss = boss_query.single().ok();
+
+    if let Ok(mut bar) = bar_query.single_mut() {
+        bar.display = match boss {
+            Some(_) => Display::Flex,
+            None => Display::None,
+        };
+    }
+
+    if let Some((boss, &Health(hp))) = boss
+        && let Ok(mut fill) = fill_query.single_mut()
+    {
+        fill.width = Val::Percent(hp as f32 / boss.max_hp as f32 * 100.0);
+    }
+}
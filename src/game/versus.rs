@@ -0,0 +1,388 @@
+use crate::GameAssets;
+use crate::GameState;
+use crate::Players;
+use crate::despawn_screen;
+use crate::net::{Attack, Connection, NetInput, Summary, TickMessage};
+
+use super::Dive;
+use super::ENEMY_BULLET_SPEED;
+use super::Enemy;
+use super::EnemyKilled;
+use super::FrontEnemies;
+use super::Hp;
+use super::InputState;
+use super::LEFT_WALL;
+use super::MyRng;
+use super::Position;
+use super::RIGHT_WALL;
+use super::Score;
+use super::TOP_WALL;
+use super::board::{ENEMY_SPACING, spawn_enemy};
+use super::slot_translation;
+use super::spawn_enemy_bullet;
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use rand::prelude::*;
+
+/// How many ticks the rival may fall behind before we wait for them.
+const INPUT_DELAY: u32 = 3;
+const MAX_ATTACKS_PER_PACKET: usize = 32;
+
+/// A rival silent for this long has left, which ends the match.
+const RIVAL_TIMEOUT: f32 = 5.;
+/// How long to wait for the rival to show up at the start of a round.
+const JOIN_TIMEOUT: f32 = 30.;
+
+const KILLS_PER_BULLET_ATTACK: u32 = 3;
+const KILLS_PER_ENEMY_ATTACK: u32 = 10;
+
+pub fn versus_plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameState::Running), start_round)
+        .add_systems(
+            PreUpdate,
+            receive_messages.run_if(resource_exists::<VersusState>),
+        )
+        .add_systems(
+            FixedUpdate,
+            (send_attacks, receive_attacks).run_if(
+                in_state(GameState::Running)
+                    .and(resource_exists::<VersusState>)
+                    .and(lockstep_ready),
+            ),
+        )
+        .add_systems(
+            FixedPostUpdate,
+            advance_tick.run_if(
+                in_state(GameState::Running)
+                    .and(resource_exists::<VersusState>)
+                    .and(lockstep_ready),
+            ),
+        )
+        .add_systems(
+            Update,
+            check_rival.run_if(in_state(GameState::Running).and(resource_exists::<VersusState>)),
+        )
+        .add_systems(
+            PostUpdate,
+            send_message.run_if(resource_exists::<VersusState>),
+        )
+        .add_systems(OnExit(GameState::Running), end_round)
+        .add_systems(
+            OnEnter(GameState::GameOver),
+            setup_result.run_if(resource_exists::<VersusResult>),
+        )
+        .add_systems(
+            OnExit(GameState::GameOver),
+            (despawn_screen::<OnVersusResult>, remove_result),
+        );
+}
+
+/// Lockstep bookkeeping for one versus round.
+#[derive(Resource, Default)]
+pub struct VersusState {
+    round: u16,
+    /// Whether `round` is settled. Only the joining side has to wait for
+    /// the host's number.
+    synced: bool,
+    local_tick: u32,
+    peer_tick: u32,
+    kills: u32,
+    next_attack_seq: u32,
+    outgoing: Vec<(u32, Attack)>,
+    last_received_seq: u32,
+    incoming: Vec<Attack>,
+    pub rival: Option<Summary>,
+    /// What the rival was doing on the latest tick heard from them
+    pub rival_input: NetInput,
+    /// Seconds since the rival was last heard from, or since the round
+    /// started
+    silence: f32,
+}
+
+impl VersusState {
+    fn timed_out(&self) -> bool {
+        let timeout = if self.rival.is_some() {
+            RIVAL_TIMEOUT
+        } else {
+            JOIN_TIMEOUT
+        };
+        self.silence > timeout
+    }
+
+    /// Whether a packet belongs to this round. Neither side gets more than
+    /// `INPUT_DELAY` ticks into a round without the other, so on the joining
+    /// side a packet that early is from the round the host just started,
+    /// not one left over from before.
+    fn accepts(&mut self, round: u16, tick: u32) -> bool {
+        if !self.synced && tick <= INPUT_DELAY {
+            self.round = round;
+            self.synced = true;
+        }
+        self.synced && round == self.round
+    }
+}
+
+/// How the last versus round ended, for the game-over screen.
+#[derive(Resource, Clone, Copy)]
+enum VersusResult {
+    Win,
+    Lose,
+    RivalLeft,
+    NoRival,
+}
+
+/// Gameplay only advances once the rival has caught up to within
+/// `INPUT_DELAY` ticks, so both boards run at the same pace.
+pub fn lockstep_ready(versus: Option<Res<VersusState>>) -> bool {
+    versus.is_none_or(|versus| versus.peer_tick + INPUT_DELAY >= versus.local_tick)
+}
+
+#[derive(Component)]
+struct OnVersusResult;
+
+/// Every round gets a new number from the host, so packets left over from
+/// the previous one are ignored.
+fn start_round(
+    mut commands: Commands,
+    players: Res<Players>,
+    connection: Option<Res<Connection>>,
+    mut next_round: Local<u16>,
+) {
+    let Some(connection) = connection.filter(|_| *players == Players::Versus) else {
+        return;
+    };
+
+    commands.insert_resource(VersusState {
+        round: *next_round,
+        synced: connection.is_host(),
+        next_attack_seq: 1,
+        ..default()
+    });
+    *next_round = next_round.wrapping_add(1);
+}
+
+fn receive_messages(mut connection: ResMut<Connection>, mut versus: ResMut<VersusState>) {
+    for message in connection.receive() {
+        if !versus.accepts(message.round, message.tick) {
+            continue;
+        }
+
+        if message.tick >= versus.peer_tick {
+            versus.rival_input = message.input;
+        }
+        versus.peer_tick = versus.peer_tick.max(message.tick);
+        versus.rival = Some(message.summary);
+        versus.silence = 0.;
+        versus.outgoing.retain(|&(seq, _)| seq > message.attack_ack);
+
+        for (seq, attack) in message.attacks {
+            if seq > versus.last_received_seq {
+                versus.last_received_seq = seq;
+                versus.incoming.push(attack);
+            }
+        }
+    }
+}
+
+fn send_message(
+    connection: Res<Connection>,
+    versus: Res<VersusState>,
+    input: Res<InputState>,
+    score: Res<Score>,
+    hp: Res<Hp>,
+    enemy_query: Query<(), With<Enemy>>,
+) {
+    connection.send(&TickMessage {
+        round: versus.round,
+        tick: versus.local_tick,
+        input: NetInput {
+            direction: f32::from(input.player_direction) as i8,
+            fire: input.player_fire,
+        },
+        summary: Summary {
+            score: score.0,
+            hp: hp.0,
+            enemies: enemy_query.iter().count() as u16,
+        },
+        attack_ack: versus.last_received_seq,
+        attacks: versus
+            .outgoing
+            .iter()
+            .take(MAX_ATTACKS_PER_PACKET)
+            .copied()
+            .collect(),
+    });
+}
+
+fn advance_tick(mut versus: ResMut<VersusState>) {
+    versus.local_tick += 1;
+}
+
+fn send_attacks(mut event_reader: EventReader<EnemyKilled>, mut versus: ResMut<VersusState>) {
+    for _ in event_reader.read() {
+        versus.kills += 1;
+
+        if versus.kills.is_multiple_of(KILLS_PER_BULLET_ATTACK) {
+            queue_attack(&mut versus, Attack::Bullets(2));
+        }
+        if versus.kills.is_multiple_of(KILLS_PER_ENEMY_ATTACK) {
+            queue_attack(&mut versus, Attack::Enemies(2));
+        }
+    }
+}
+
+fn queue_attack(versus: &mut VersusState, attack: Attack) {
+    let seq = versus.next_attack_seq;
+    versus.next_attack_seq += 1;
+    versus.outgoing.push((seq, attack));
+}
+
+fn receive_attacks(
+    mut commands: Commands,
+    mut versus: ResMut<VersusState>,
+    enemy_query: Query<(&Transform, &Position, Option<&Dive>), With<Enemy>>,
+    front_enemies: Res<FrontEnemies>,
+    mut rng: ResMut<MyRng>,
+) {
+    // Highest enemy per column, so extra enemies stack on top of the formation
+    let mut tops: HashMap<usize, (usize, Vec3)> = HashMap::new();
+    for (transform, &Position { row, col }, dive) in &enemy_query {
+        let translation = slot_translation(transform, dive);
+        let top = tops.entry(col).or_insert((row, translation));
+        if row > top.0 {
+            *top = (row, translation);
+        }
+    }
+
+    for attack in std::mem::take(&mut versus.incoming) {
+        match attack {
+            Attack::Enemies(amount) => {
+                for _ in 0..amount {
+                    let Some(&col) = front_enemies.0.keys().choose(&mut rng.0) else {
+                        break;
+                    };
+                    if let Some((row, translation)) = tops.get_mut(&col) {
+                        *row += 1;
+                        translation.y += ENEMY_SPACING;
+                        let position = Position { row: *row, col };
+                        spawn_enemy(&mut commands, *translation, position, Enemy::Normal);
+                    }
+                }
+            }
+            Attack::Bullets(amount) => {
+                for _ in 0..amount {
+                    let x = rng.0.random_range(LEFT_WALL..=RIGHT_WALL);
+                    spawn_enemy_bullet(
+                        &mut commands,
+                        Vec3::new(x, TOP_WALL - 10., 0.),
+                        Vec2::NEG_Y * ENEMY_BULLET_SPEED,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Ends the match once the rival is out of lives, or has gone quiet for too
+/// long. A rival who hasn't shown up yet gets longer.
+fn check_rival(
+    mut versus: ResMut<VersusState>,
+    time: Res<Time<Real>>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    versus.silence += time.delta_secs();
+
+    let rival_out = versus.rival.is_some_and(|rival| rival.hp == 0);
+    if rival_out || versus.timed_out() {
+        state.set(GameState::GameOver);
+    }
+}
+
+/// Also stops the messages, which only go out while a round is on.
+fn end_round(mut commands: Commands, versus: Option<Res<VersusState>>, hp: Res<Hp>) {
+    let Some(versus) = versus else {
+        return;
+    };
+
+    let result = if versus.timed_out() {
+        if versus.rival.is_some() {
+            VersusResult::RivalLeft
+        } else {
+            VersusResult::NoRival
+        }
+    } else if hp.0 > 0 {
+        VersusResult::Win
+    } else {
+        VersusResult::Lose
+    };
+    commands.insert_resource(result);
+    commands.remove_resource::<VersusState>();
+}
+
+fn setup_result(mut commands: Commands, result: Res<VersusResult>, assets: Res<GameAssets>) {
+    let text = match *result {
+        VersusResult::Win => "YOU WIN",
+        VersusResult::Lose => "YOU LOSE",
+        VersusResult::RivalLeft => "RIVAL LEFT",
+        VersusResult::NoRival => "NO RIVAL",
+    };
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            top: Val::Px(60.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        children![(
+            Text::new(text),
+            TextFont {
+                font: assets.font_press_start.clone(),
+                font_size: 40.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+            TextShadow::default(),
+        )],
+        OnVersusResult,
+    ));
+}
+
+fn remove_result(mut commands: Commands) {
+    commands.remove_resource::<VersusResult>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joining_side_takes_the_hosts_round() {
+        let mut versus = VersusState {
+            round: 4,
+            ..default()
+        };
+
+        // Late packets from a round the host is still finishing
+        assert!(!versus.accepts(9, 500));
+        assert!(versus.accepts(12, 0));
+        assert_eq!(versus.round, 12);
+        assert!(versus.accepts(12, 40));
+        assert!(!versus.accepts(9, 501));
+    }
+
+    #[test]
+    fn host_keeps_its_round() {
+        let mut versus = VersusState {
+            round: 4,
+            synced: true,
+            ..default()
+        };
+        assert!(!versus.accepts(3, 0));
+        assert!(versus.accepts(4, 0));
+        assert_eq!(versus.round, 4);
+    }
+}
@@ -1,17 +1,17 @@
 use crate::GameAssets;
 use crate::GameState;
 use crate::despawn_screen;
+use crate::game::{CampaignStage, DailyChallenge, RunStats};
+use crate::profile::Profile;
+use crate::ui::button;
 
 use bevy::prelude::*;
 
-const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
-const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
-
 pub fn game_over_plugin(app: &mut App) {
     app.add_systems(OnEnter(GameState::GameOver), setup_game_over_screen)
         .add_systems(
             Update,
-            (button_style, button_interaction).run_if(in_state(GameState::GameOver)),
+            button_interaction.run_if(in_state(GameState::GameOver)),
         )
         .add_systems(
             OnExit(GameState::GameOver),
@@ -25,12 +25,22 @@ struct OnGameOverScreen;
 #[derive(Component)]
 struct TryAgainButton;
 
+#[derive(Component)]
+struct StagesButton;
+
 #[derive(Component)]
 struct ExitButton;
 
-fn setup_game_over_screen(mut commands: Commands, assets: Res<GameAssets>) {
-    commands.spawn((
-        (
+fn setup_game_over_screen(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    stats: Res<RunStats>,
+    stage: Res<CampaignStage>,
+    daily: Res<DailyChallenge>,
+    profile: Res<Profile>,
+) {
+    let root = commands
+        .spawn((
             Node {
                 flex_direction: FlexDirection::Column,
                 padding: UiRect::all(Val::Px(4.0)),
@@ -40,69 +50,111 @@ fn setup_game_over_screen(mut commands: Commands, assets: Res<GameAssets>) {
                 justify_content: JustifyContent::Center,
                 ..default()
             },
-            children![
-                (TryAgainButton, button("Try Again", &assets)),
-                (ExitButton, button("Exit", &assets))
-            ],
-        ),
-        OnGameOverScreen,
-    ));
-}
+            OnGameOverScreen,
+        ))
+        .id();
 
-fn button(name: &str, assets: &GameAssets) -> impl Bundle {
-    (
-        Button,
+    commands.spawn((
+        Text::new(stats.outcome.title()),
+        TextFont {
+            font: assets.font_press_start.clone(),
+            font_size: 30.0,
+            ..default()
+        },
         Node {
-            width: Val::Px(300.0),
-            height: Val::Px(65.0),
-            border: UiRect::all(Val::Px(5.0)),
-            // horizontally center child text
-            justify_content: JustifyContent::Center,
-            // vertically center child text
-            align_items: AlignItems::Center,
-            margin: UiRect::all(Val::Px(4.0)),
+            margin: UiRect::bottom(Val::Px(20.0)),
             ..default()
         },
-        BorderColor(Color::BLACK),
-        BorderRadius::MAX,
-        BackgroundColor(NORMAL_BUTTON),
-        children![(
-            Text::new(name),
-            TextFont {
-                font: assets.font_press_start.clone(),
-                font_size: 25.0,
+        ChildOf(root),
+    ));
+
+    // Summary of the run
+    let minutes = (stats.time_survived / 60.) as u32;
+    let seconds = stats.time_survived as u32 % 60;
+    let kills = stats
+        .kills
+        .iter()
+        .map(|(enemy, count)| format!("{enemy} {count}"))
+        .collect::<Vec<_>>()
+        .join("  ");
+    let mut lines = vec![
+        format!("Final score: {}", stats.score),
+        format!("Difficulty: {}", stats.difficulty.name()),
+        format!("Mode: {}", stats.mode.name()),
+        format!("Waves cleared: {}", stats.waves_cleared),
+        format!("Time survived: {minutes}:{seconds:02}"),
+        format!(
+            "Shots: {}  Hits: {}  Accuracy: {:.0}%",
+            stats.shots,
+            stats.hits,
+            stats.accuracy() * 100.
+        ),
+        format!("Damage taken: {}", stats.damage_taken),
+        format!("Shields lost: {}", stats.shields_lost),
+        format!("Kills: {kills}"),
+    ];
+    // Endless runs compare against the best of their mode and difficulty.
+    // The profile may not have this run yet.
+    if stage.0.is_none() && daily.0.is_none() {
+        let best = profile
+            .mode_best(stats.mode, stats.difficulty)
+            .max(stats.score);
+        lines.insert(3, format!("Best: {best}"));
+    }
+
+    commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                margin: UiRect::bottom(Val::Px(20.0)),
                 ..default()
             },
-            TextColor(Color::srgb(0.9, 0.9, 0.9)),
-            TextShadow::default(),
-        )],
-    )
-}
-
-#[allow(clippy::type_complexity)]
-fn button_style(
-    mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor, &mut BorderColor),
-        (Changed<Interaction>, With<Button>),
-    >,
-) {
-    for (&interaction, mut color, mut border_color) in &mut interaction_query {
-        match interaction {
-            Interaction::Hovered | Interaction::Pressed => {
-                *color = HOVERED_BUTTON.into();
-                border_color.0 = Color::WHITE;
+            ChildOf(root),
+        ))
+        .with_children(|parent| {
+            for line in lines {
+                parent.spawn((
+                    Text::new(line),
+                    TextFont {
+                        font: assets.font_press_start.clone(),
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                    Node {
+                        margin: UiRect::all(Val::Px(4.0)),
+                        ..default()
+                    },
+                ));
             }
-            Interaction::None => {
-                *color = NORMAL_BUTTON.into();
-                border_color.0 = Color::BLACK;
-            }
-        }
+        });
+
+    let buttons = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ChildOf(root),
+        ))
+        .id();
+    commands.spawn((
+        TryAgainButton,
+        button("Try Again", &assets),
+        ChildOf(buttons),
+    ));
+    // Back to the campaign, where the stage cleared may have unlocked another
+    if stage.0.is_some() {
+        commands.spawn((StagesButton, button("Stages", &assets), ChildOf(buttons)));
     }
+    commands.spawn((ExitButton, button("Exit", &assets), ChildOf(buttons)));
 }
 
 #[allow(clippy::type_complexity)]
 fn button_interaction(
     try_again_button: Query<&Interaction, (Changed<Interaction>, With<TryAgainButton>)>,
+    stages_button: Query<&Interaction, (Changed<Interaction>, With<StagesButton>)>,
     exit_button: Query<&Interaction, (Changed<Interaction>, With<ExitButton>)>,
     mut game_state: ResMut<NextState<GameState>>,
     mut event_writer: EventWriter<AppExit>,
@@ -111,6 +163,10 @@ fn button_interaction(
         game_state.set(GameState::Running);
     }
 
+    if let Ok(&Interaction::Pressed) = stages_button.single() {
+        game_state.set(GameState::StageSelect);
+    }
+
     if let Ok(&Interaction::Pressed) = exit_button.single() {
         event_writer.write(AppExit::Success);
     }
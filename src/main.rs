@@ -1,6 +1,16 @@
+mod achievements_screen;
+mod daily_screen;
 mod game;
 mod game_over;
 mod menu;
+mod net;
+mod profile;
+mod stage_select_screen;
+mod ui;
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
 
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
@@ -19,6 +29,9 @@ fn main() {
                 }),
         )
         .init_state::<GameState>()
+        .init_resource::<Players>()
+        .init_resource::<Difficulty>()
+        .init_resource::<GameMode>()
         .add_loading_state(
             LoadingState::new(GameState::AssetLoading)
                 .continue_to_state(GameState::Menu)
@@ -29,6 +42,12 @@ fn main() {
             menu::menu_plugin,
             game::game_plugin,
             game_over::game_over_plugin,
+            net::net_plugin,
+            profile::profile_plugin,
+            achievements_screen::achievements_screen_plugin,
+            stage_select_screen::stage_select_screen_plugin,
+            daily_screen::daily_screen_plugin,
+            ui::ui_plugin,
         ))
         .run();
 }
@@ -44,6 +63,25 @@ fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands
     }
 }
 
+/// Where files that outlive a run, like the profile, are kept.
+fn data_path(file: &str) -> PathBuf {
+    let dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .unwrap_or_default();
+    dir.join("shoot-the-space-guys").join(file)
+}
+
+/// Writes one of the files under `data_path`, creating the directory on the
+/// first save.
+fn write_data_file(file: &str, contents: String) -> io::Result<()> {
+    let path = data_path(file);
+    path.parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| fs::write(&path, contents))
+}
+
 #[derive(AssetCollection, Resource)]
 struct GameAssets {
     #[asset(path = "sounds/laser.ogg")]
@@ -52,6 +90,18 @@ struct GameAssets {
     font_press_start: Handle<Font>,
     #[asset(path = "sprites/heart.png")]
     sprite_heart: Handle<Image>,
+    #[asset(path = "levels/default.level")]
+    level: Handle<game::Level>,
+    #[asset(
+        paths(
+            "levels/stage1.level",
+            "levels/stage2.level",
+            "levels/stage3.level",
+            "levels/stage4.level"
+        ),
+        collection(typed)
+    )]
+    stage_levels: Vec<Handle<game::Level>>,
 }
 
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
@@ -61,4 +111,102 @@ enum GameState {
     Running,
     Menu,
     GameOver,
+    Achievements,
+    Editor,
+    StageSelect,
+    Daily,
+}
+
+#[derive(Resource, Clone, Copy, Default, Eq, PartialEq, Debug)]
+enum Players {
+    #[default]
+    One,
+    Two,
+    Versus,
+}
+
+#[derive(Resource, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Debug)]
+enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Insane,
+}
+
+impl Difficulty {
+    const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Insane,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Insane => "Insane",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Insane,
+            Difficulty::Insane => Difficulty::Easy,
+        }
+    }
+}
+
+/// The rules a run is played by, picked in the menu for endless runs.
+#[derive(Resource, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Debug)]
+enum GameMode {
+    #[default]
+    Standard,
+    /// Highest score before the clock runs out
+    TimeAttack,
+    /// Killed enemies keep coming back, last as long as possible
+    Survival,
+    /// A single life for double the score
+    OneLife,
+}
+
+impl GameMode {
+    const ALL: [GameMode; 4] = [
+        GameMode::Standard,
+        GameMode::TimeAttack,
+        GameMode::Survival,
+        GameMode::OneLife,
+    ];
+
+    /// How the mode is written in the save and profile files.
+    fn id(self) -> &'static str {
+        match self {
+            GameMode::Standard => "standard",
+            GameMode::TimeAttack => "time_attack",
+            GameMode::Survival => "survival",
+            GameMode::OneLife => "one_life",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            GameMode::Standard => "Standard",
+            GameMode::TimeAttack => "Time Attack",
+            GameMode::Survival => "Survival",
+            GameMode::OneLife => "One-Life",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            GameMode::Standard => GameMode::TimeAttack,
+            GameMode::TimeAttack => GameMode::Survival,
+            GameMode::Survival => GameMode::OneLife,
+            GameMode::OneLife => GameMode::Standard,
+        }
+    }
 }
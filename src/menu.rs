@@ -1,34 +1,63 @@
+use crate::Difficulty;
 use crate::GameAssets;
+use crate::GameMode;
 use crate::GameState;
+use crate::Players;
 use crate::despawn_screen;
+use crate::game::CampaignStage;
+use crate::game::DailyChallenge;
+use crate::game::SavedRun;
+use crate::net::Connection;
+use crate::ui::button;
 
 use bevy::prelude::*;
 
-const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
-const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
-
 pub fn menu_plugin(app: &mut App) {
     app.add_systems(OnEnter(GameState::Menu), setup_menu)
-        .add_systems(Update, button_system.run_if(in_state(GameState::Menu)))
+        .add_systems(Update, button_interaction.run_if(in_state(GameState::Menu)))
         .add_systems(OnExit(GameState::Menu), despawn_screen::<OnMenu>);
 }
 
 #[derive(Component)]
 struct OnMenu;
 
-fn setup_menu(mut commands: Commands, assets: Res<GameAssets>) {
-    commands.spawn((
-        (
-            Node {
-                flex_direction: FlexDirection::Column,
-                width: Val::Percent(100.0),
-                height: Val::Percent(100.0),
-                align_items: AlignItems::Center,
-                justify_content: JustifyContent::SpaceEvenly,
-                ..default()
-            },
-            children![
-                (
+#[derive(Component)]
+struct StartButton(Players);
+
+#[derive(Component)]
+struct ContinueButton;
+
+/// Goes to another screen when pressed.
+#[derive(Component)]
+struct ScreenButton(GameState);
+
+/// Cycles through the difficulties when pressed.
+#[derive(Component)]
+struct DifficultyButton;
+
+/// Cycles through the game modes when pressed.
+#[derive(Component)]
+struct ModeButton;
+
+fn setup_menu(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    connection: Option<Res<Connection>>,
+    difficulty: Res<Difficulty>,
+    mode: Res<GameMode>,
+) {
+    let root = commands
+        .spawn((
+            (
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::SpaceEvenly,
+                    ..default()
+                },
+                children![(
                     Text::new("SHOOT THE SPACE GUYS"),
                     TextFont {
                         font: assets.font_press_start.clone(),
@@ -37,59 +66,176 @@ fn setup_menu(mut commands: Commands, assets: Res<GameAssets>) {
                     },
                     TextColor(Color::srgb(0.9, 0.9, 0.9)),
                     TextShadow::default(),
-                ),
-                (
-                    Button,
-                    Node {
-                        width: Val::Px(300.0),
-                        height: Val::Px(65.0),
-                        border: UiRect::all(Val::Px(5.0)),
-                        // horizontally center child text
-                        justify_content: JustifyContent::Center,
-                        // vertically center child text
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    BorderColor(Color::BLACK),
-                    BorderRadius::MAX,
-                    BackgroundColor(NORMAL_BUTTON),
-                    children![(
-                        Text::new("Start Game"),
+                ),],
+            ),
+            OnMenu,
+        ))
+        .id();
+
+    let mut buttons = commands.spawn((
+        Node {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        ChildOf(root),
+    ));
+
+    // Only offered when there's a suspended run to resume
+    if SavedRun::exists() {
+        buttons.with_child((ContinueButton, button("Continue", &assets)));
+    }
+
+    buttons.with_children(|parent| {
+        parent.spawn((StartButton(Players::One), button("1 Player", &assets)));
+        parent.spawn((StartButton(Players::Two), button("2 Players", &assets)));
+    });
+
+    // Only offered when launched with a versus connection
+    if connection.is_some() {
+        buttons.with_child((StartButton(Players::Versus), button("Versus", &assets)));
+    }
+
+    commands.spawn((
+        Node::default(),
+        ChildOf(root),
+        children![
+            (
+                ScreenButton(GameState::StageSelect),
+                button("Campaign", &assets)
+            ),
+            (ScreenButton(GameState::Daily), button("Daily", &assets)),
+        ],
+    ));
+
+    // Settings side by side, so the menu still fits the window
+    commands.spawn((
+        Node::default(),
+        ChildOf(root),
+        children![
+            (
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                children![
+                    (
+                        Text::new("Difficulty"),
+                        TextFont {
+                            font: assets.font_press_start.clone(),
+                            font_size: 20.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                    ),
+                    (DifficultyButton, button(difficulty.name(), &assets)),
+                ],
+            ),
+            (
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                children![
+                    (
+                        Text::new("Mode"),
                         TextFont {
                             font: assets.font_press_start.clone(),
-                            font_size: 25.0,
+                            font_size: 20.0,
                             ..default()
                         },
                         TextColor(Color::srgb(0.9, 0.9, 0.9)),
-                        TextShadow::default(),
-                    )]
-                )
-            ],
-        ),
-        OnMenu,
+                    ),
+                    (ModeButton, button(mode.name(), &assets)),
+                ],
+            ),
+        ],
+    ));
+
+    commands.spawn((
+        Node::default(),
+        ChildOf(root),
+        children![
+            (
+                ScreenButton(GameState::Achievements),
+                button("Achievements", &assets)
+            ),
+            (
+                ScreenButton(GameState::Editor),
+                button("Level Editor", &assets)
+            ),
+        ],
     ));
 }
 
-#[allow(clippy::type_complexity)]
-fn button_system(
-    mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor, &mut BorderColor),
-        (Changed<Interaction>, With<Button>),
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn button_interaction(
+    mut commands: Commands,
+    continue_button: Query<&Interaction, (Changed<Interaction>, With<ContinueButton>)>,
+    start_buttons: Query<(&Interaction, &StartButton), Changed<Interaction>>,
+    screen_buttons: Query<(&Interaction, &ScreenButton), Changed<Interaction>>,
+    difficulty_buttons: Query<
+        (&Interaction, &Children),
+        (Changed<Interaction>, With<DifficultyButton>),
     >,
+    mode_buttons: Query<(&Interaction, &Children), (Changed<Interaction>, With<ModeButton>)>,
+    mut text_query: Query<&mut Text>,
+    mut players: ResMut<Players>,
+    mut difficulty: ResMut<Difficulty>,
+    mut mode: ResMut<GameMode>,
+    mut stage: ResMut<CampaignStage>,
+    mut daily: ResMut<DailyChallenge>,
     mut game_state: ResMut<NextState<GameState>>,
 ) {
-    for (interaction, mut color, mut border_color) in &mut interaction_query {
-        match *interaction {
-            Interaction::Pressed => {
-                game_state.set(GameState::Running);
+    if let Ok(&Interaction::Pressed) = continue_button.single()
+        && let Some(saved_run) = SavedRun::take()
+    {
+        *players = Players::One;
+        *difficulty = saved_run.difficulty;
+        *mode = saved_run.mode;
+        *stage = saved_run.stage;
+        *daily = saved_run.daily;
+        commands.insert_resource(saved_run);
+        game_state.set(GameState::Running);
+    }
+
+    for (&interaction, &StartButton(count)) in &start_buttons {
+        if interaction == Interaction::Pressed {
+            *players = count;
+            *stage = CampaignStage(None);
+            *daily = DailyChallenge(None);
+            // Versus plays by its own rules
+            if count == Players::Versus {
+                *mode = GameMode::Standard;
             }
-            Interaction::Hovered => {
-                *color = HOVERED_BUTTON.into();
-                border_color.0 = Color::WHITE;
+            game_state.set(GameState::Running);
+        }
+    }
+
+    for (&interaction, &ScreenButton(screen)) in &screen_buttons {
+        if interaction == Interaction::Pressed {
+            game_state.set(screen);
+        }
+    }
+
+    for (&interaction, children) in &difficulty_buttons {
+        if interaction == Interaction::Pressed {
+            *difficulty = difficulty.next();
+            let mut texts = text_query.iter_many_mut(children);
+            while let Some(mut text) = texts.fetch_next() {
+                **text = difficulty.name().to_string();
             }
-            Interaction::None => {
-                *color = NORMAL_BUTTON.into();
-                border_color.0 = Color::BLACK;
+        }
+    }
+
+    for (&interaction, children) in &mode_buttons {
+        if interaction == Interaction::Pressed {
+            *mode = mode.next();
+            let mut texts = text_query.iter_many_mut(children);
+            while let Some(mut text) = texts.fetch_next() {
+                **text = mode.name().to_string();
             }
         }
     }
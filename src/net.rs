@@ -0,0 +1,375 @@
+//! UDP transport for versus games.
+//!
+//! One instance hosts with `--host <addr>` and the other joins with
+//! `--join <addr>`, e.g. `--host 127.0.0.1:7777` and `--join 127.0.0.1:7777`
+//! for two processes on the same machine. The host numbers the rounds, and
+//! the joining side picks the number up from the start of each round.
+//! `--loopback` instead runs a local test server in a background thread that
+//! mirrors every tick and sends a few attacks back, so versus mode can be
+//! tried with a single process.
+
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::thread;
+
+use bevy::prelude::*;
+
+const MAGIC: &[u8; 4] = b"STSG";
+const MAX_PACKET_SIZE: usize = 1200;
+
+const LOOPBACK_ATTACK_INTERVAL: u32 = 300;
+const LOOPBACK_HP: u8 = 9;
+
+pub fn net_plugin(app: &mut App) {
+    match connection_from_args(std::env::args().skip(1)) {
+        Ok(Some(connection)) => {
+            app.insert_resource(connection);
+        }
+        Ok(None) => {}
+        Err(err) => error!("Failed to set up versus connection: {err}"),
+    }
+}
+
+#[derive(Resource)]
+pub struct Connection {
+    socket: UdpSocket,
+    peer: Option<SocketAddr>,
+    hosting: bool,
+}
+
+impl Connection {
+    pub fn host(addr: &str) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        info!("Hosting versus game on {}", socket.local_addr()?);
+        Ok(Self {
+            socket,
+            peer: None,
+            hosting: true,
+        })
+    }
+
+    pub fn join(addr: &str) -> io::Result<Self> {
+        let peer = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address to join"))?;
+        let local: SocketAddr = if peer.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0; 8], 0).into()
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.set_nonblocking(true)?;
+        info!("Joining versus game on {peer}");
+        Ok(Self {
+            socket,
+            peer: Some(peer),
+            hosting: false,
+        })
+    }
+
+    pub fn loopback() -> io::Result<Self> {
+        let server = UdpSocket::bind("127.0.0.1:0")?;
+        let addr = server.local_addr()?;
+        thread::spawn(move || loopback_server(server));
+        Self::join(&addr.to_string())
+    }
+
+    /// The host decides which round is being played.
+    pub fn is_host(&self) -> bool {
+        self.hosting
+    }
+
+    pub fn send(&self, message: &TickMessage) {
+        if let Some(peer) = self.peer
+            && let Err(err) = self.socket.send_to(&message.encode(), peer)
+        {
+            warn!("Failed to send to {peer}: {err}");
+        }
+    }
+
+    /// Drains every pending packet. The host learns its peer's address from
+    /// the first valid packet it receives.
+    pub fn receive(&mut self) -> Vec<TickMessage> {
+        let mut messages = Vec::new();
+        let mut buf = [0; MAX_PACKET_SIZE];
+        loop {
+            match self.socket.recv_from(&mut buf) {
+                Ok((len, from)) => {
+                    if self.peer.is_some_and(|peer| peer != from) {
+                        continue;
+                    }
+                    if let Some(message) = TickMessage::decode(&buf[..len]) {
+                        self.peer = Some(from);
+                        messages.push(message);
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => {
+                    warn!("Failed to receive: {err}");
+                    break;
+                }
+            }
+        }
+        messages
+    }
+}
+
+/// Parses `--host <addr>`, `--join <addr>` or `--loopback` from the command
+/// line, returning `None` when no versus option was given.
+fn connection_from_args(mut args: impl Iterator<Item = String>) -> io::Result<Option<Connection>> {
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--host" => return Connection::host(&address(&mut args, &arg)?).map(Some),
+            "--join" => return Connection::join(&address(&mut args, &arg)?).map(Some),
+            "--loopback" => return Connection::loopback().map(Some),
+            _ => {}
+        }
+    }
+
+    Ok(None)
+}
+
+fn address(args: &mut impl Iterator<Item = String>, flag: &str) -> io::Result<String> {
+    args.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{flag} needs an address"),
+        )
+    })
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct NetInput {
+    pub direction: i8,
+    pub fire: bool,
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Summary {
+    pub score: u32,
+    pub hp: u8,
+    pub enemies: u16,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Attack {
+    Enemies(u8),
+    Bullets(u8),
+}
+
+/// Sent every frame. Attacks are resent until the peer acknowledges their
+/// sequence number in `attack_ack`.
+#[derive(Clone, Debug)]
+pub struct TickMessage {
+    pub round: u16,
+    pub tick: u32,
+    pub input: NetInput,
+    pub summary: Summary,
+    pub attack_ack: u32,
+    pub attacks: Vec<(u32, Attack)>,
+}
+
+impl TickMessage {
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(32 + self.attacks.len() * 6);
+        buf.extend_from_slice(MAGIC);
+        buf.extend_from_slice(&self.round.to_le_bytes());
+        buf.extend_from_slice(&self.tick.to_le_bytes());
+        buf.push(self.input.direction as u8);
+        buf.push(self.input.fire as u8);
+        buf.extend_from_slice(&self.summary.score.to_le_bytes());
+        buf.push(self.summary.hp);
+        buf.extend_from_slice(&self.summary.enemies.to_le_bytes());
+        buf.extend_from_slice(&self.attack_ack.to_le_bytes());
+        buf.push(self.attacks.len() as u8);
+        for &(seq, attack) in &self.attacks {
+            let (kind, amount) = match attack {
+                Attack::Enemies(amount) => (0, amount),
+                Attack::Bullets(amount) => (1, amount),
+            };
+            buf.extend_from_slice(&seq.to_le_bytes());
+            buf.push(kind);
+            buf.push(amount);
+        }
+        buf
+    }
+
+    fn decode(buf: &[u8]) -> Option<Self> {
+        let mut reader = Reader(buf);
+        if reader.take::<4>()? != *MAGIC {
+            return None;
+        }
+
+        let round = u16::from_le_bytes(reader.take()?);
+        let tick = u32::from_le_bytes(reader.take()?);
+        let input = NetInput {
+            direction: reader.byte()? as i8,
+            fire: reader.byte()? != 0,
+        };
+        if !(-1..=1).contains(&input.direction) {
+            return None;
+        }
+        let summary = Summary {
+            score: u32::from_le_bytes(reader.take()?),
+            hp: reader.byte()?,
+            enemies: u16::from_le_bytes(reader.take()?),
+        };
+        let attack_ack = u32::from_le_bytes(reader.take()?);
+        let attacks = (0..reader.byte()?)
+            .map(|_| {
+                let seq = u32::from_le_bytes(reader.take()?);
+                let attack = match (reader.byte()?, reader.byte()?) {
+                    (0, amount) => Attack::Enemies(amount),
+                    (1, amount) => Attack::Bullets(amount),
+                    _ => return None,
+                };
+                Some((seq, attack))
+            })
+            .collect::<Option<_>>()?;
+
+        Some(Self {
+            round,
+            tick,
+            input,
+            summary,
+            attack_ack,
+            attacks,
+        })
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (head, tail) = self.0.split_first_chunk()?;
+        self.0 = tail;
+        Some(*head)
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        self.take::<1>().map(|[byte]| byte)
+    }
+}
+
+/// Stands in for a second player: echoes each tick straight back so lockstep
+/// never stalls, and sends a volley of bullets every few seconds.
+fn loopback_server(socket: UdpSocket) {
+    let mut buf = [0; MAX_PACKET_SIZE];
+    let mut round = 0;
+    let mut last_attack_tick = 0;
+    let mut next_seq = 1;
+    let mut attack_ack = 0;
+    let mut pending: Vec<(u32, Attack)> = Vec::new();
+
+    while let Ok((len, from)) = socket.recv_from(&mut buf) {
+        let Some(message) = TickMessage::decode(&buf[..len]) else {
+            continue;
+        };
+
+        if message.round != round {
+            round = message.round;
+            last_attack_tick = 0;
+            next_seq = 1;
+            attack_ack = 0;
+            pending.clear();
+        }
+
+        attack_ack = message
+            .attacks
+            .iter()
+            .map(|&(seq, _)| seq)
+            .fold(attack_ack, u32::max);
+        pending.retain(|&(seq, _)| seq > message.attack_ack);
+
+        if message.tick >= last_attack_tick + LOOPBACK_ATTACK_INTERVAL {
+            last_attack_tick = message.tick;
+            pending.push((next_seq, Attack::Bullets(3)));
+            next_seq += 1;
+        }
+
+        let reply = TickMessage {
+            round,
+            tick: message.tick,
+            input: NetInput::default(),
+            summary: Summary {
+                score: 0,
+                hp: LOOPBACK_HP,
+                enemies: 0,
+            },
+            attack_ack,
+            attacks: pending.clone(),
+        };
+        if socket.send_to(&reply.encode(), from).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message() -> TickMessage {
+        TickMessage {
+            round: 3,
+            tick: 1234,
+            input: NetInput {
+                direction: -1,
+                fire: true,
+            },
+            summary: Summary {
+                score: 4500,
+                hp: 2,
+                enemies: 17,
+            },
+            attack_ack: 8,
+            attacks: vec![(9, Attack::Enemies(2)), (10, Attack::Bullets(3))],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let sent = message();
+        let received = TickMessage::decode(&sent.encode()).unwrap();
+        assert_eq!(received.round, 3);
+        assert_eq!(received.tick, 1234);
+        assert_eq!(received.input.direction, -1);
+        assert!(received.input.fire);
+        assert_eq!(received.summary.score, 4500);
+        assert_eq!(received.summary.hp, 2);
+        assert_eq!(received.summary.enemies, 17);
+        assert_eq!(received.attack_ack, 8);
+        assert!(matches!(
+            received.attacks[..],
+            [(9, Attack::Enemies(2)), (10, Attack::Bullets(3))]
+        ));
+    }
+
+    #[test]
+    fn malformed_packets_are_rejected() {
+        let buf = message().encode();
+
+        // Every truncation, down to nothing
+        for len in 0..buf.len() {
+            assert!(TickMessage::decode(&buf[..len]).is_none());
+        }
+
+        let mut wrong_magic = buf.clone();
+        wrong_magic[0] = b'X';
+        assert!(TickMessage::decode(&wrong_magic).is_none());
+
+        // The direction byte comes right after the magic, round and tick
+        let mut wrong_direction = buf.clone();
+        wrong_direction[10] = 2;
+        assert!(TickMessage::decode(&wrong_direction).is_none());
+
+        // The kind of the last attack is its second to last byte
+        let mut wrong_attack = buf;
+        let kind = wrong_attack.len() - 2;
+        wrong_attack[kind] = 7;
+        assert!(TickMessage::decode(&wrong_attack).is_none());
+    }
+}
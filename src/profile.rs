@@ -0,0 +1,275 @@
+//! Lifetime totals, achievements and campaign progress, kept across runs in a small text file
+//! of `key value` lines in the data directory.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+
+use bevy::prelude::*;
+
+use crate::Difficulty;
+use crate::GameMode;
+use crate::data_path;
+use crate::write_data_file;
+
+const PROFILE_FILE: &str = "profile.txt";
+/// Keeps corrupt `stage_best` lines from filling the profile with stages.
+const MAX_STAGES: usize = 100;
+
+pub fn profile_plugin(app: &mut App) {
+    app.insert_resource(Profile::load());
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Achievement {
+    FirstBlood,
+    Flawless,
+    Sharpshooter,
+    ComboMaster,
+    Centurion,
+    BossSlayer,
+    Survivor,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 7] = [
+        Achievement::FirstBlood,
+        Achievement::Flawless,
+        Achievement::Sharpshooter,
+        Achievement::ComboMaster,
+        Achievement::Centurion,
+        Achievement::BossSlayer,
+        Achievement::Survivor,
+    ];
+
+    fn id(self) -> &'static str {
+        match self {
+            Achievement::FirstBlood => "first_blood",
+            Achievement::Flawless => "flawless",
+            Achievement::Sharpshooter => "sharpshooter",
+            Achievement::ComboMaster => "combo_master",
+            Achievement::Centurion => "centurion",
+            Achievement::BossSlayer => "boss_slayer",
+            Achievement::Survivor => "survivor",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Achievement::FirstBlood => "First Blood",
+            Achievement::Flawless => "Flawless",
+            Achievement::Sharpshooter => "Sharpshooter",
+            Achievement::ComboMaster => "Combo Master",
+            Achievement::Centurion => "Centurion",
+            Achievement::BossSlayer => "Boss Slayer",
+            Achievement::Survivor => "Survivor",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Achievement::FirstBlood => "Shoot down a space guy",
+            Achievement::Flawless => "Clear a wave without getting hit",
+            Achievement::Sharpshooter => "Clear a wave with 75% accuracy",
+            Achievement::ComboMaster => "Max out the combo multiplier",
+            Achievement::Centurion => "Kill 100 enemies in one run",
+            Achievement::BossSlayer => "Defeat a boss",
+            Achievement::Survivor => "Survive for 5 minutes",
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Profile {
+    pub runs_played: u32,
+    pub total_kills: u32,
+    pub best_accuracy: f32,
+    pub longest_survival: f32,
+    pub achievements: Vec<Achievement>,
+    /// Campaign stages cleared so far, which unlocks the one after them
+    pub stages_cleared: usize,
+    /// Best score on each campaign stage, by index and difficulty
+    stage_best: BTreeMap<(usize, Difficulty), u32>,
+    /// Best score of endless runs, by mode and difficulty
+    mode_best: BTreeMap<(GameMode, Difficulty), u32>,
+}
+
+impl Profile {
+    /// Loads the profile, starting a fresh one if there is none yet.
+    fn load() -> Self {
+        match fs::read_to_string(data_path(PROFILE_FILE)) {
+            Ok(contents) => Self::parse(&contents),
+            Err(err) => {
+                if err.kind() != io::ErrorKind::NotFound {
+                    warn!("Failed to read profile: {err}");
+                }
+                Self::default()
+            }
+        }
+    }
+
+    /// Unknown or malformed lines are skipped, so old profiles keep loading.
+    fn parse(contents: &str) -> Self {
+        let mut profile = Self::default();
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once(' ') else {
+                continue;
+            };
+            match key {
+                "runs_played" => profile.runs_played = value.parse().unwrap_or_default(),
+                "total_kills" => profile.total_kills = value.parse().unwrap_or_default(),
+                "best_accuracy" => profile.best_accuracy = value.parse().unwrap_or_default(),
+                "longest_survival" => {
+                    profile.longest_survival = value.parse().unwrap_or_default();
+                }
+                "stages_cleared" => profile.stages_cleared = value.parse().unwrap_or_default(),
+                "stage_best" => {
+                    // Scores from before difficulties were kept count as Normal
+                    let (stage, difficulty, score) =
+                        match value.split_whitespace().collect::<Vec<_>>()[..] {
+                            [stage, score] => (stage, Difficulty::Normal.name(), score),
+                            [stage, difficulty, score] => (stage, difficulty, score),
+                            _ => continue,
+                        };
+                    if let (Ok(stage), Some(&difficulty), Ok(score)) = (
+                        stage.parse(),
+                        Difficulty::ALL.iter().find(|d| d.name() == difficulty),
+                        score.parse(),
+                    ) && stage < MAX_STAGES
+                    {
+                        profile.record_stage_score(stage, difficulty, score);
+                    }
+                }
+                "mode_best" => {
+                    if let [mode, difficulty, score] =
+                        value.split_whitespace().collect::<Vec<_>>()[..]
+                        && let (Some(&mode), Some(&difficulty), Ok(score)) = (
+                            GameMode::ALL.iter().find(|m| m.id() == mode),
+                            Difficulty::ALL.iter().find(|d| d.name() == difficulty),
+                            score.parse(),
+                        )
+                    {
+                        profile.record_mode_score(mode, difficulty, score);
+                    }
+                }
+                "achievement" => {
+                    if let Some(&achievement) = Achievement::ALL.iter().find(|a| a.id() == value) {
+                        profile.achievements.push(achievement);
+                    }
+                }
+                _ => {}
+            }
+        }
+        profile
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = write_data_file(PROFILE_FILE, self.contents()) {
+            warn!("Failed to save profile: {err}");
+        }
+    }
+
+    /// The profile file `parse` reads back.
+    fn contents(&self) -> String {
+        let mut contents = format!(
+            "runs_played {}\ntotal_kills {}\nbest_accuracy {}\nlongest_survival {}\n",
+            self.runs_played, self.total_kills, self.best_accuracy, self.longest_survival
+        );
+        contents += &format!("stages_cleared {}\n", self.stages_cleared);
+        for ((stage, difficulty), score) in &self.stage_best {
+            contents += &format!("stage_best {stage} {} {score}\n", difficulty.name());
+        }
+        for ((mode, difficulty), score) in &self.mode_best {
+            contents += &format!("mode_best {} {} {score}\n", mode.id(), difficulty.name());
+        }
+        for achievement in &self.achievements {
+            contents += &format!("achievement {}\n", achievement.id());
+        }
+        contents
+    }
+
+    /// Returns whether `achievement` was newly unlocked.
+    pub fn unlock(&mut self, achievement: Achievement) -> bool {
+        if self.achievements.contains(&achievement) {
+            return false;
+        }
+        self.achievements.push(achievement);
+        true
+    }
+
+    pub fn stage_unlocked(&self, stage: usize) -> bool {
+        stage <= self.stages_cleared
+    }
+
+    pub fn record_stage_score(&mut self, stage: usize, difficulty: Difficulty, score: u32) {
+        let best = self.stage_best.entry((stage, difficulty)).or_default();
+        *best = (*best).max(score);
+    }
+
+    pub fn stage_best(&self, stage: usize, difficulty: Difficulty) -> u32 {
+        self.stage_best
+            .get(&(stage, difficulty))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn record_mode_score(&mut self, mode: GameMode, difficulty: Difficulty, score: u32) {
+        let best = self.mode_best.entry((mode, difficulty)).or_default();
+        *best = (*best).max(score);
+    }
+
+    pub fn mode_best(&self, mode: GameMode, difficulty: Difficulty) -> u32 {
+        self.mode_best
+            .get(&(mode, difficulty))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Records a cleared stage, unlocking the next one.
+    pub fn clear_stage(&mut self, stage: usize, difficulty: Difficulty, score: u32) {
+        self.stages_cleared = self.stages_cleared.max(stage + 1);
+        self.record_stage_score(stage, difficulty, score);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stage_bests_are_kept_per_difficulty() {
+        let mut profile = Profile::default();
+        profile.clear_stage(0, Difficulty::Hard, 900);
+        profile.record_stage_score(0, Difficulty::Easy, 1500);
+        profile.record_stage_score(0, Difficulty::Hard, 400);
+
+        let parsed = Profile::parse(&profile.contents());
+        assert_eq!(parsed.stage_best(0, Difficulty::Hard), 900);
+        assert_eq!(parsed.stage_best(0, Difficulty::Easy), 1500);
+        assert_eq!(parsed.stage_best(0, Difficulty::Normal), 0);
+    }
+
+    #[test]
+    fn mode_bests_are_kept_per_difficulty() {
+        let mut profile = Profile::default();
+        profile.record_mode_score(GameMode::TimeAttack, Difficulty::Insane, 3000);
+        profile.record_mode_score(GameMode::TimeAttack, Difficulty::Insane, 1000);
+        profile.record_mode_score(GameMode::Survival, Difficulty::Insane, 50);
+
+        let parsed = Profile::parse(&profile.contents());
+        assert_eq!(
+            parsed.mode_best(GameMode::TimeAttack, Difficulty::Insane),
+            3000
+        );
+        assert_eq!(parsed.mode_best(GameMode::Survival, Difficulty::Insane), 50);
+        assert_eq!(parsed.mode_best(GameMode::TimeAttack, Difficulty::Easy), 0);
+    }
+
+    #[test]
+    fn old_stage_bests_count_as_normal() {
+        let profile =
+            Profile::parse("stage_best 1 700\nstage_best 1 Nightmare 5\nstage_best 999 1\n");
+        assert_eq!(profile.stage_best(1, Difficulty::Normal), 700);
+        assert_eq!(profile.stage_best.len(), 1);
+    }
+}
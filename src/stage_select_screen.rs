@@ -0,0 +1,132 @@
+use crate::Difficulty;
+use crate::GameAssets;
+use crate::GameMode;
+use crate::GameState;
+use crate::Players;
+use crate::despawn_screen;
+use crate::game::{CampaignStage, DailyChallenge, Levels};
+use crate::profile::Profile;
+use crate::ui::{Inactive, button, text, wide_button};
+
+use bevy::prelude::*;
+
+/// Wide enough for the longest stage name.
+const STAGE_BUTTON_WIDTH: f32 = 400.0;
+
+const UNLOCKED_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+const LOCKED_COLOR: Color = Color::srgb(0.4, 0.4, 0.4);
+
+pub fn stage_select_screen_plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameState::StageSelect), setup_stage_select)
+        .add_systems(
+            Update,
+            button_interaction.run_if(in_state(GameState::StageSelect)),
+        )
+        .add_systems(
+            OnExit(GameState::StageSelect),
+            despawn_screen::<OnStageSelectScreen>,
+        );
+}
+
+#[derive(Component)]
+struct OnStageSelectScreen;
+
+/// Only on unlocked stages.
+#[derive(Component)]
+struct StageButton(usize);
+
+#[derive(Component)]
+struct BackButton;
+
+fn setup_stage_select(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    levels: Res<Levels>,
+    profile: Res<Profile>,
+    difficulty: Res<Difficulty>,
+) {
+    let root = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::SpaceEvenly,
+                ..default()
+            },
+            OnStageSelectScreen,
+        ))
+        .id();
+
+    commands.spawn((text("CAMPAIGN", 40.0, &assets), ChildOf(root)));
+
+    let stages = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ChildOf(root),
+        ))
+        .id();
+
+    for (index, level) in levels.stages.iter().enumerate() {
+        let unlocked = profile.stage_unlocked(index);
+        let best = profile.stage_best(index, *difficulty);
+        let mut row = commands.spawn((
+            Node {
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ChildOf(stages),
+        ));
+        if unlocked {
+            row.with_child((
+                StageButton(index),
+                wide_button(&level.name, STAGE_BUTTON_WIDTH, &assets),
+            ));
+        } else {
+            row.with_child((Inactive, wide_button("Locked", STAGE_BUTTON_WIDTH, &assets)));
+        }
+        row.with_child((
+            text(format!("{} best: {best}", difficulty.name()), 14.0, &assets),
+            TextColor(if unlocked {
+                UNLOCKED_COLOR
+            } else {
+                LOCKED_COLOR
+            }),
+            Node {
+                margin: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+        ));
+    }
+
+    commands.spawn((BackButton, button("Back", &assets), ChildOf(root)));
+}
+
+fn button_interaction(
+    stage_buttons: Query<(&Interaction, &StageButton), Changed<Interaction>>,
+    back_button: Query<&Interaction, (Changed<Interaction>, With<BackButton>)>,
+    mut players: ResMut<Players>,
+    mut mode: ResMut<GameMode>,
+    mut stage: ResMut<CampaignStage>,
+    mut daily: ResMut<DailyChallenge>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for (&interaction, &StageButton(index)) in &stage_buttons {
+        if interaction == Interaction::Pressed {
+            *players = Players::One;
+            // Stages are cleared by beating their boss
+            *mode = GameMode::Standard;
+            *stage = CampaignStage(Some(index));
+            *daily = DailyChallenge(None);
+            game_state.set(GameState::Running);
+        }
+    }
+
+    if let Ok(&Interaction::Pressed) = back_button.single() {
+        game_state.set(GameState::Menu);
+    }
+}
@@ -0,0 +1,85 @@
+//! Buttons and text shared by the menu screens.
+
+use crate::GameAssets;
+
+use bevy::prelude::*;
+
+const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
+const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
+
+const BUTTON_WIDTH: f32 = 300.0;
+
+pub fn ui_plugin(app: &mut App) {
+    app.add_systems(Update, button_style);
+}
+
+/// A button that stays dark when hovered, like a locked stage.
+#[derive(Component)]
+pub struct Inactive;
+
+pub fn text(text: impl Into<String>, font_size: f32, assets: &GameAssets) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font: assets.font_press_start.clone(),
+            font_size,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+    )
+}
+
+pub fn button(name: &str, assets: &GameAssets) -> impl Bundle {
+    wide_button(name, BUTTON_WIDTH, assets)
+}
+
+pub fn wide_button(name: &str, width: f32, assets: &GameAssets) -> impl Bundle {
+    (
+        Button,
+        Node {
+            width: Val::Px(width),
+            height: Val::Px(65.0),
+            border: UiRect::all(Val::Px(5.0)),
+            // horizontally center child text
+            justify_content: JustifyContent::Center,
+            // vertically center child text
+            align_items: AlignItems::Center,
+            margin: UiRect::all(Val::Px(4.0)),
+            ..default()
+        },
+        BorderColor(Color::BLACK),
+        BorderRadius::MAX,
+        BackgroundColor(NORMAL_BUTTON),
+        children![(
+            Text::new(name),
+            TextFont {
+                font: assets.font_press_start.clone(),
+                font_size: 25.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+            TextShadow::default(),
+        )],
+    )
+}
+
+#[allow(clippy::type_complexity)]
+fn button_style(
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, &mut BorderColor),
+        (Changed<Interaction>, With<Button>, Without<Inactive>),
+    >,
+) {
+    for (&interaction, mut color, mut border_color) in &mut interaction_query {
+        match interaction {
+            Interaction::Hovered | Interaction::Pressed => {
+                *color = HOVERED_BUTTON.into();
+                border_color.0 = Color::WHITE;
+            }
+            Interaction::None => {
+                *color = NORMAL_BUTTON.into();
+                border_color.0 = Color::BLACK;
+            }
+        }
+    }
+}